@@ -0,0 +1,113 @@
+#![allow(clippy::needless_return)]
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use d_ig::{header::HeaderStyle, target::Target, GitIgnore, IgnoreGroups};
+use std::{
+    env::temp_dir,
+    fs::{create_dir_all, remove_dir_all, write},
+    hint::black_box,
+    path::PathBuf,
+};
+
+const LINES: usize = 100_000;
+const SECTION_LINES: usize = 1_000;
+
+/// A .gitignore of `LINES` lines: some unmanaged rules followed by banner sections of
+/// comments and rules, like one grown by hand and by d-ig over years.
+fn synthetic(lines: usize) -> String {
+    let mut content = String::with_capacity(lines * 24);
+
+    for idx in 0..SECTION_LINES {
+        content.push_str(&format!("/local/unmanaged-{idx}.log\n"));
+    }
+
+    for section in 1..lines / SECTION_LINES {
+        content.push_str(&HeaderStyle::Banner.header(&format!("Generated{section}")));
+        content.push('\n');
+
+        for idx in 0..SECTION_LINES - 1 {
+            match idx % 10 {
+                0 => content.push_str(&format!("# Build output of tool {idx}\n")),
+                _ => content.push_str(&format!("**/build-{section}-{idx}/*.o\n")),
+            }
+        }
+    }
+
+    return content;
+}
+
+fn fixture() -> PathBuf {
+    let dir = temp_dir().join(format!("d-ig-bench-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+
+    let path = dir.join(".gitignore");
+    write(&path, synthetic(LINES)).unwrap();
+
+    return path;
+}
+
+fn catalog(c: &mut Criterion) {
+    c.bench_function("catalog/parse", |b| {
+        b.iter(|| IgnoreGroups::parse(Target::Gitignore).unwrap())
+    });
+
+    c.bench_function("catalog/parse_and_get", |b| {
+        b.iter(|| {
+            let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+            black_box(igs.get("Rust").unwrap());
+            black_box(igs.get("Python").unwrap());
+        })
+    });
+}
+
+fn large_file(c: &mut Criterion) {
+    let path = fixture();
+    let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+
+    let mut group = c.benchmark_group("gitignore_100k");
+    group.sample_size(20);
+
+    group.bench_function("load", |b| b.iter(|| GitIgnore::open(&path).unwrap()));
+
+    let gitignore = GitIgnore::open(&path).unwrap();
+    group.bench_function("render", |b| b.iter(|| gitignore.render()));
+
+    group.bench_function("write", |b| {
+        b.iter_batched(
+            || GitIgnore::open(&path).unwrap(),
+            |gitignore| gitignore.write().unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("add_group", |b| {
+        b.iter_batched(
+            || GitIgnore::open(&path).unwrap(),
+            |mut gitignore| {
+                gitignore
+                    .add_group(igs.get("Rust").unwrap(), false, &igs)
+                    .unwrap();
+                gitignore
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("add_item", |b| {
+        b.iter_batched(
+            || GitIgnore::open(&path).unwrap(),
+            |mut gitignore| {
+                gitignore.add_item("/not/in/the/file");
+                gitignore
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+
+    remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+criterion_group!(benches, catalog, large_file);
+criterion_main!(benches);
@@ -1,10 +1,15 @@
-use crate::{
+use d_ig::{
+    export::Format,
     r#const::{
-        ADD, ADD_ITEM, ADD_ITEM_S, ADD_S, CLEAR, CLEAR_S, CREATE, CREATE_S, FORCE_ADD, FORCE_ADD_S,
-        HELP, HELP_S, LIST, LIST_EXHAUSTIVE, LIST_EXHAUSTIVE_S, LIST_S, REMOVE, REMOVE_ITEM,
-        REMOVE_ITEM_S, REMOVE_S, VERSION, VERSION_S,
+        ADD, ADD_ITEM, ADD_ITEM_S, ADD_S, ALIASES_FLAG, CATEGORY, CLEAR, CLEAR_S, COLOR, CREATE,
+        CREATE_S, ERROR_FORMAT, EXPLAIN, EXPORT, FORCE, FORCE_ADD, FORCE_ADD_S, HEADER_STYLE, HELP,
+        HELP_S, HISTORY, HISTORY_S, IMPORT, INTERACTIVE, INTERACTIVE_S, KEEP_GOING, KEEP_GOING_S,
+        LIST, LIST_EXHAUSTIVE, LIST_EXHAUSTIVE_S, LIST_S, ON_EXISTS, PICK, PREVIEW, PROGRAM_NAME,
+        PROGRAM_VERSION, REMOVE, REMOVE_ITEM, REMOVE_ITEM_S, REMOVE_S, SEARCH, SEARCH_S, STATUS,
+        STDOUT, SYNC, TARGET, TO, UNDO, UNDO_S, UNTRACK, UNTRACK_S, UPDATE, VERIFY, VERIFY_S,
+        VERSION, VERSION_S, WORKSPACE, YES, YES_S,
     },
-    Error, PROGRAM_NAME, PROGRAM_VERSION,
+    Category, Change, Error,
 };
 use std::env;
 
@@ -15,20 +20,33 @@ enum ParserState {
     Remove,
     RemoveItem,
     List,
+    Category,
+    Undo,
     None,
 }
 
 #[derive(Debug)]
-pub enum Change {
-    AddG(String),
-    AddGF(String),
-    AddI(String),
-    RemoveG(String),
-    RemoveI(String),
+pub enum Command {
+    Explain(Vec<String>),
+    Pick,
+    Workspace {
+        yes: bool,
+    },
+    Status,
+    Preview(Vec<String>),
+    Sync,
+    Update(Vec<String>),
+    Import(Option<String>),
+    Export {
+        format: Format,
+        stdout: bool,
+        force: bool,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Args {
+    pub command: Option<Command>,
     pub changes: Vec<Change>,
     pub create: bool,
     pub version: bool,
@@ -36,6 +54,15 @@ pub struct Args {
     pub list: Option<Vec<String>>,
     pub list_exhaustive: bool,
     pub clear: bool,
+    pub untrack: bool,
+    pub keep_going: bool,
+    pub interactive: bool,
+    pub undo: Option<usize>,
+    pub history: bool,
+    pub verify: bool,
+    pub search: bool,
+    pub category: Option<Category>,
+    pub aliases: bool,
 }
 
 impl Args {
@@ -43,6 +70,7 @@ impl Args {
         let args: Vec<String> = env::args()
             .enumerate()
             .filter_map(|(idx, x)| if idx > 0 { Some(x) } else { None })
+            .filter(|x| !Args::is_global_option(x))
             .collect();
 
         let arg_count = args.len();
@@ -55,9 +83,27 @@ impl Args {
         let mut state = ParserState::None;
         let mut list = None;
         let mut list_exhaustive = false;
+        let mut untrack = false;
+        let mut keep_going = false;
+        let mut interactive = false;
+        let mut undo = None;
+        let mut history = false;
+        let mut verify = false;
+        let mut search = false;
+        let mut category = None;
+        let mut aliases = false;
 
         if arg_count == 0 {
-            return Err(Error(format!("Must enter command --help, -h for help.",)));
+            return Err(Error::Usage(
+                "Must enter command --help, -h for help.".to_string(),
+            ));
+        }
+
+        if let Some(command) = Args::parse_command(&args)? {
+            return Ok(Args {
+                command: Some(command),
+                ..Default::default()
+            });
         }
 
         for (idx, arg) in args.into_iter().enumerate() {
@@ -82,13 +128,25 @@ impl Args {
                         create = true;
                     }
                 }
+                UNTRACK | UNTRACK_S => match list {
+                    Some(ref mut lst) => lst.push(arg),
+                    None => untrack = true,
+                },
+                INTERACTIVE | INTERACTIVE_S => match list {
+                    Some(ref mut lst) => lst.push(arg),
+                    None => interactive = true,
+                },
+                KEEP_GOING | KEEP_GOING_S => match list {
+                    Some(ref mut lst) => lst.push(arg),
+                    None => keep_going = true,
+                },
                 VERSION | VERSION_S => {
                     if Args::set_state_or_list(&mut state, &mut list, ParserState::None, arg) {
                         state = ParserState::None;
                         version = true;
 
                         if arg_count > 1 {
-                            return Err(Error(format!(
+                            return Err(Error::Usage(format!(
                                 "{}, {} must be called without any other arguments.",
                                 VERSION, VERSION_S
                             )));
@@ -101,19 +159,43 @@ impl Args {
                         clear = true;
 
                         if arg_count > 1 {
-                            return Err(Error(format!(
+                            return Err(Error::Usage(format!(
                                 "{}, {} must be called without any other arguments.",
                                 CLEAR, CLEAR_S
                             )));
                         }
                     }
                 }
+                UNDO | UNDO_S => {
+                    if Args::set_state_or_list(&mut state, &mut list, ParserState::Undo, arg) {
+                        undo = Some(1);
+
+                        if idx > 0 || arg_count > 2 {
+                            return Err(Error::Usage(format!(
+                                "{}, {} must be called without any other arguments except a step count.",
+                                UNDO, UNDO_S
+                            )));
+                        }
+                    }
+                }
+                HISTORY | HISTORY_S => {
+                    if Args::set_state_or_list(&mut state, &mut list, ParserState::None, arg) {
+                        history = true;
+
+                        if arg_count > 1 {
+                            return Err(Error::Usage(format!(
+                                "{}, {} must be called without any other arguments.",
+                                HISTORY, HISTORY_S
+                            )));
+                        }
+                    }
+                }
                 HELP | HELP_S => {
                     if Args::set_state_or_list(&mut state, &mut list, ParserState::None, arg) {
                         help = true;
 
                         if arg_count > 1 {
-                            return Err(Error(format!(
+                            return Err(Error::Usage(format!(
                                 "{}, {} must be called without any other arguments.",
                                 HELP, HELP_S
                             )));
@@ -125,20 +207,48 @@ impl Args {
                         list = Some(Vec::new());
 
                         if idx > 0 {
-                            return Err(Error(format!(
+                            return Err(Error::Usage(format!(
                                 "{}, {} must be the first arguments",
                                 LIST, LIST_S
                             )));
                         }
                     }
                 }
+                VERIFY | VERIFY_S => {
+                    if Args::set_state_or_list(&mut state, &mut list, ParserState::List, arg) {
+                        list = Some(Vec::new());
+                        verify = true;
+
+                        if idx > 0 {
+                            return Err(Error::Usage(format!(
+                                "{}, {} must be the first arguments",
+                                VERIFY, VERIFY_S
+                            )));
+                        }
+                    }
+                }
+                SEARCH | SEARCH_S => {
+                    if Args::set_state_or_list(&mut state, &mut list, ParserState::List, arg) {
+                        list = Some(Vec::new());
+                        search = true;
+
+                        if idx > 0 {
+                            return Err(Error::Usage(format!(
+                                "{}, {} must be the first arguments",
+                                SEARCH, SEARCH_S
+                            )));
+                        }
+                    }
+                }
+                CATEGORY => state = ParserState::Category,
+                ALIASES_FLAG => aliases = true,
                 LIST_EXHAUSTIVE | LIST_EXHAUSTIVE_S => {
                     if Args::set_state_or_list(&mut state, &mut list, ParserState::List, arg) {
                         list = Some(Vec::new());
                         list_exhaustive = true;
 
                         if idx > 0 {
-                            return Err(Error(format!(
+                            return Err(Error::Usage(format!(
                                 "{}, {} must be the first arguments",
                                 LIST_EXHAUSTIVE, LIST_EXHAUSTIVE_S
                             )));
@@ -156,8 +266,21 @@ impl Args {
                             lst.push(arg);
                         }
                     }
+                    ParserState::Category => {
+                        category = Some(Category::parse(&arg)?);
+                        state = ParserState::List;
+                    }
+                    ParserState::Undo => match arg.parse() {
+                        Ok(steps) => undo = Some(steps),
+                        Err(_) => {
+                            return Err(Error::Usage(format!(
+                                "{}, {} expects a number of steps, not \"{}\".",
+                                UNDO, UNDO_S, arg
+                            )))
+                        }
+                    },
                     ParserState::None => {
-                        return Err(Error(format!(
+                        return Err(Error::Usage(format!(
                             "Invalid command \"{}\". {} for help.",
                             arg, HELP
                         )));
@@ -166,7 +289,26 @@ impl Args {
             }
         }
 
+        if let ParserState::Category = state {
+            return Err(Error::Usage(format!(
+                "{CATEGORY} expects root, global or community."
+            )));
+        }
+
+        if aliases && (list.is_none() || verify || search || list_exhaustive) {
+            return Err(Error::Usage(format!(
+                "{ALIASES_FLAG} can only be used with {LIST}, {LIST_S}."
+            )));
+        }
+
+        if category.is_some() && (list.is_none() || verify || search) {
+            return Err(Error::Usage(format!(
+                "{CATEGORY} can only be used with {LIST}, {LIST_S} or {LIST_EXHAUSTIVE}, {LIST_EXHAUSTIVE_S}."
+            )));
+        }
+
         return Ok(Args {
+            command: None,
             create,
             version,
             help,
@@ -174,9 +316,130 @@ impl Args {
             list_exhaustive,
             changes,
             clear,
+            untrack,
+            keep_going,
+            interactive,
+            undo,
+            history,
+            verify,
+            search,
+            category,
+            aliases,
         });
     }
 
+    /// Options that may appear anywhere, written as `--name=value`.
+    const GLOBAL_OPTIONS: [&'static str; 5] =
+        [COLOR, ERROR_FORMAT, HEADER_STYLE, ON_EXISTS, TARGET];
+
+    fn is_global_option(arg: &str) -> bool {
+        return Args::GLOBAL_OPTIONS
+            .iter()
+            .any(|name| arg.starts_with(&format!("{name}=")));
+    }
+
+    /// Finds the value of a global `--name=value` option.
+    pub fn global_option(name: &str) -> Option<String> {
+        let prefix = format!("{name}=");
+
+        return env::args()
+            .skip(1)
+            .find_map(|x| x.strip_prefix(&prefix).map(|x| x.to_string()));
+    }
+
+    fn parse_command(args: &[String]) -> Result<Option<Command>, Error> {
+        let rest = args[1..].to_vec();
+
+        match args[0].as_str() {
+            EXPLAIN => {
+                if rest.is_empty() {
+                    return Err(Error::Usage(format!(
+                        "{EXPLAIN} requires at least one path."
+                    )));
+                }
+
+                return Ok(Some(Command::Explain(rest)));
+            }
+            PICK => {
+                if !rest.is_empty() {
+                    return Err(Error::Usage(format!(
+                        "{PICK} must be called without any other arguments."
+                    )));
+                }
+
+                return Ok(Some(Command::Pick));
+            }
+            STATUS => {
+                if !rest.is_empty() {
+                    return Err(Error::Usage(format!(
+                        "{STATUS} must be called without any other arguments."
+                    )));
+                }
+
+                return Ok(Some(Command::Status));
+            }
+            SYNC => {
+                if !rest.is_empty() {
+                    return Err(Error::Usage(format!(
+                        "{SYNC} must be called without any other arguments."
+                    )));
+                }
+
+                return Ok(Some(Command::Sync));
+            }
+            UPDATE => return Ok(Some(Command::Update(rest))),
+            PREVIEW => return Ok(Some(Command::Preview(rest))),
+            EXPORT => {
+                let mut format = None;
+                let mut stdout = false;
+                let mut force = false;
+                let mut rest = rest.into_iter();
+
+                while let Some(arg) = rest.next() {
+                    match arg.as_str() {
+                        TO => match rest.next() {
+                            Some(value) => format = Some(Format::parse(&value)?),
+                            None => return Err(Error::Usage(format!("{TO} expects a format."))),
+                        },
+                        STDOUT => stdout = true,
+                        FORCE => force = true,
+                        _ => {
+                            return Err(Error::Usage(format!(
+                                "Invalid {EXPORT} argument \"{arg}\". {HELP} for help."
+                            )))
+                        }
+                    }
+                }
+
+                return match format {
+                    Some(format) => Ok(Some(Command::Export {
+                        format,
+                        stdout,
+                        force,
+                    })),
+                    None => Err(Error::Usage(format!("{EXPORT} requires {TO} FORMAT."))),
+                };
+            }
+            IMPORT => {
+                if rest.len() > 1 {
+                    return Err(Error::Usage(format!("{IMPORT} takes at most one file.")));
+                }
+
+                return Ok(Some(Command::Import(rest.into_iter().next())));
+            }
+            WORKSPACE => match rest.iter().map(|x| x.as_str()).collect::<Vec<_>>()[..] {
+                [] => return Ok(Some(Command::Workspace { yes: false })),
+                [YES | YES_S] => return Ok(Some(Command::Workspace { yes: true })),
+                _ => {
+                    return Err(Error::Usage(format!(
+                        "{WORKSPACE} only accepts {YES}, {YES_S}."
+                    )))
+                }
+            },
+            _ => return Ok(None),
+        }
+    }
+
     fn set_state_or_list(
         state: &mut ParserState,
         list: &mut Option<Vec<String>>,
@@ -203,7 +466,10 @@ impl Args {
         info: &str,
         min_width: usize,
     ) {
-        let mut cmd = format!("{long}, {short}");
+        let mut cmd = match short.is_empty() {
+            true => long.to_string(),
+            false => format!("{long}, {short}"),
+        };
 
         if let Some(arg) = arguments {
             cmd += &format!(" [{arg}]");
@@ -231,9 +497,12 @@ impl Args {
         let ignore_groups = Some("IGNORE_GROUPS");
         let ingore_items = Some("IGNORE_ITEMS");
         let filters = Some("FILTERS");
+        let patterns = Some("PATTERNS");
+        let paths = Some("PATHS");
+        let steps = Some("STEPS");
 
         string += &format!("{} ({})\n\n", PROGRAM_NAME, PROGRAM_VERSION);
-        string += &format!("Commands:\n");
+        string += "Commands:\n";
 
         Args::add_command_to_string(
             &mut string,
@@ -244,6 +513,24 @@ impl Args {
             min_width,
         );
 
+        Args::add_command_to_string(
+            &mut string,
+            INTERACTIVE,
+            INTERACTIVE_S,
+            None,
+            "With --create, asks which groups to use and shows the file before creating it.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{ON_EXISTS}=merge|replace|fail"),
+            "",
+            None,
+            "With --create, merges into, replaces or keeps an existing .gitignore without asking.",
+            min_width,
+        );
+
         Args::add_command_to_string(
             &mut string,
             ADD,
@@ -262,6 +549,24 @@ impl Args {
             min_width,
         );
 
+        Args::add_command_to_string(
+            &mut string,
+            UNTRACK,
+            UNTRACK_S,
+            None,
+            "Stops tracking files that groups added alongside it would ignore.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            KEEP_GOING,
+            KEEP_GOING_S,
+            None,
+            "Applies the valid changes even if some of them have problems.",
+            min_width,
+        );
+
         Args::add_command_to_string(
             &mut string,
             REMOVE,
@@ -298,6 +603,177 @@ impl Args {
             min_width,
         );
 
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{CATEGORY} root|global|community"),
+            "",
+            None,
+            "With --list or --list-exhaustive, only lists groups of this category.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            ALIASES_FLAG,
+            "",
+            None,
+            "With --list, lists the other names groups can be given by instead.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            SEARCH,
+            SEARCH_S,
+            patterns,
+            "Lists the ignore groups whose rules or comments contain one of the given patterns.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            PICK,
+            "",
+            None,
+            "Opens a full-screen picker to check and uncheck ignore groups.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{WORKSPACE} [{YES}, {YES_S}]"),
+            "",
+            None,
+            "Adds the groups each subproject needs to its own .gitignore.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            STATUS,
+            "",
+            None,
+            "Shows which sections were edited locally or changed in the catalog.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            PREVIEW,
+            "",
+            ignore_groups,
+            "Shows the sections, repeated rules and newly ignored files adding groups would give.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            UPDATE,
+            "",
+            ignore_groups,
+            "Regenerates sections the catalog changed that were not edited locally.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            SYNC,
+            "",
+            None,
+            "Adds and removes groups and items until .gitignore matches .d-ig.toml.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            VERIFY,
+            VERIFY_S,
+            ignore_groups,
+            "Fails without writing if groups are missing, edited, outdated or repeated outside of d-ig sections.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            IMPORT,
+            "",
+            Some("FILE"),
+            "Converts a gitignore.io file, .gitignore by default, into managed sections.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{EXPORT} {TO} FORMAT [{STDOUT}] [{FORCE}]"),
+            "",
+            None,
+            "Writes the managed rules as a .dockerignore, .npmignore, .prettierignore, .eslintignore or .hgignore.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            UNDO,
+            UNDO_S,
+            steps,
+            "Restores .gitignore to how it was the given number of writes ago (default 1).",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            HISTORY,
+            HISTORY_S,
+            None,
+            "Lists previous versions of .gitignore kept for undo.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            EXPLAIN,
+            "",
+            paths,
+            "Shows which section and line of .gitignore ignores each path.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{ERROR_FORMAT}=json|text"),
+            "",
+            None,
+            "Prints errors as text or as a single line of json (default text).",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{COLOR}=auto|always|never"),
+            "",
+            None,
+            "Colors output when auto detects a terminal, always or never (default auto).",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{HEADER_STYLE}=banner|compact|marker"),
+            "",
+            None,
+            "Writes section headers in this style instead of the one the file uses.",
+            min_width,
+        );
+
+        Args::add_command_to_string(
+            &mut string,
+            &format!("{TARGET}=gitignore|dockerignore"),
+            "",
+            None,
+            "Manages .gitignore or .dockerignore with its own catalog (default gitignore).",
+            min_width,
+        );
+
         Args::add_command_to_string(
             &mut string,
             VERSION,
@@ -1,6 +1,7 @@
-pub static IGNORE_FILE: &'static str = include_str!("ignores.txt");
+pub static IGNORE_FILE: &str = include_str!("ignores.txt");
+pub static DOCKER_IGNORE_FILE: &str = include_str!("dockerignores.txt");
 
-pub const RED: &str = "\x1b[33m";
+pub const RED: &str = "\x1b[31m";
 pub const GREEN: &str = "\x1b[32m";
 pub const YELLOW: &str = "\x1b[33m";
 pub const BLUE: &str = "\x1b[36m";
@@ -36,6 +37,81 @@ pub const LIST_S: &str = "-l";
 pub const LIST_EXHAUSTIVE: &str = "--list-exhaustive";
 pub const LIST_EXHAUSTIVE_S: &str = "-le";
 
+pub const CATEGORY: &str = "--category";
+pub const ALIASES_FLAG: &str = "--aliases";
+
+/// Other names for catalog groups, matched without regard to case. Names of templates
+/// that github/gitignore has since renamed are kept here so they keep working.
+pub const ALIASES: &[(&str, &str)] = &[
+    ("node", "Node"),
+    ("js", "Node"),
+    ("javascript", "Node"),
+    ("ts", "Node"),
+    ("typescript", "Node"),
+    ("py", "Python"),
+    ("golang", "Go"),
+    ("cpp", "C++"),
+    ("vscode", "Global/VisualStudioCode"),
+    ("idea", "Global/JetBrains"),
+    ("intellij", "Global/JetBrains"),
+    ("mac", "Global/macOS"),
+    ("macos", "Global/macOS"),
+    ("osx", "Global/macOS"),
+    ("vim", "Global/Vim"),
+    ("emacs", "Global/Emacs"),
+    ("linux", "Global/Linux"),
+    ("windows", "Global/Windows"),
+    ("xcode", "Global/Xcode"),
+    // Renamed upstream.
+    ("Global/OSX", "Global/macOS"),
+];
+
+pub const SEARCH: &str = "--search";
+pub const SEARCH_S: &str = "-s";
+
+pub const INTERACTIVE: &str = "--interactive";
+pub const INTERACTIVE_S: &str = "-i";
+
+pub const UNTRACK: &str = "--untrack";
+pub const UNTRACK_S: &str = "-u";
+
+pub const SYNC: &str = "sync";
+pub const MANIFEST: &str = ".d-ig.toml";
+pub const LOCK: &str = ".d-ig.lock";
+
+pub const VERIFY: &str = "--verify";
+pub const VERIFY_S: &str = "-vf";
+
+pub const KEEP_GOING: &str = "--keep-going";
+pub const KEEP_GOING_S: &str = "-k";
+
+pub const ERROR_FORMAT: &str = "--error-format";
+pub const YES: &str = "--yes";
+pub const YES_S: &str = "-y";
+
+pub const COLOR: &str = "--color";
+pub const ON_EXISTS: &str = "--on-exists";
+pub const HEADER_STYLE: &str = "--header-style";
+pub const TARGET: &str = "--target";
+
+pub const UNDO: &str = "--undo";
+pub const UNDO_S: &str = "-ud";
+
+pub const HISTORY: &str = "--history";
+pub const HISTORY_S: &str = "-hs";
+
+pub const EXPLAIN: &str = "explain";
+pub const PICK: &str = "pick";
+pub const WORKSPACE: &str = "workspace";
+pub const STATUS: &str = "status";
+pub const PREVIEW: &str = "preview";
+pub const UPDATE: &str = "update";
+pub const IMPORT: &str = "import";
+pub const EXPORT: &str = "export";
+pub const TO: &str = "--to";
+pub const STDOUT: &str = "--stdout";
+pub const FORCE: &str = "--force";
+
 pub const CLEAR: &str = "--clear";
 pub const CLEAR_S: &str = "-cl";
 
@@ -45,8 +121,23 @@ pub const GIT_IGNORE: &str = "test.gitignore";
 #[cfg(not(feature = "test"))]
 pub const GIT_IGNORE: &str = ".gitignore";
 
+pub const DOCKER_IGNORE: &str = ".dockerignore";
+
+pub const HISTORY_DIR: &str = "d-ig/history";
+pub const HISTORY_LIMIT: usize = 20;
+
 pub const PROGRAM_NAME: &str = "Dechow Git Ignore Builder (d-ig)";
 pub const PROGRAM_VERSION: &str = "0.1.1";
 
+/// Name of the section holding content outside of any d-ig header.
+pub const UNMANAGED: &str = "";
+
+/// Starts the line after a section header recording how the section was generated.
+pub const META_PREFIX: &str = "#@d-ig";
+pub const META_VERSION: u32 = 1;
+/// Where the templates in the embedded catalog come from.
+pub const CATALOG_SOURCE: &str = "github/gitignore";
+pub const DOCKER_CATALOG_SOURCE: &str = "d-ig/dockerignore";
+
 pub const TITLE_WRAPPER_OPEN: &str = "#==========================================[";
 pub const TITLE_WRAPPER_CLOSE: &str = "]==========================================#";
@@ -0,0 +1,126 @@
+use std::{env, fs::read_dir, path::Path};
+
+/// Files or directories that mark a project, with the catalog groups they suggest.
+/// Names starting with `*.` match any file with that extension.
+pub const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    ("Cargo.toml", &["Rust"]),
+    ("package.json", &["Node"]),
+    ("pyproject.toml", &["Python"]),
+    ("setup.py", &["Python"]),
+    ("requirements.txt", &["Python"]),
+    ("Pipfile", &["Python"]),
+    ("go.mod", &["Go"]),
+    ("pom.xml", &["Maven", "Java"]),
+    ("build.gradle", &["Gradle", "Java"]),
+    ("build.gradle.kts", &["Gradle", "Kotlin"]),
+    ("Gemfile", &["Ruby"]),
+    ("composer.json", &["Composer"]),
+    ("artisan", &["Laravel"]),
+    ("*.csproj", &["VisualStudio"]),
+    ("*.sln", &["VisualStudio"]),
+    ("CMakeLists.txt", &["CMake"]),
+    ("pubspec.yaml", &["Dart", "Flutter"]),
+    ("mix.exs", &["Elixir"]),
+    ("Package.swift", &["Swift"]),
+    ("stack.yaml", &["Haskell"]),
+    ("*.cabal", &["Haskell"]),
+    ("build.zig", &["Zig"]),
+    ("*.tf", &["Terraform"]),
+    ("Project.toml", &["Julia"]),
+    ("DESCRIPTION", &["R"]),
+    ("build.sbt", &["Scala"]),
+    ("project.godot", &["Godot"]),
+    ("ProjectSettings", &["Unity"]),
+    ("elm.json", &["Elm"]),
+    ("dune-project", &["OCaml"]),
+    ("*.nimble", &["Nim"]),
+    ("*.tex", &["TeX"]),
+];
+
+/// Editor settings directories and the catalog groups they suggest.
+pub const EDITOR_MARKERS: &[(&str, &[&str])] = &[
+    (".vscode", &["Global/VisualStudioCode"]),
+    (".idea", &["Global/JetBrains"]),
+    ("*.sublime-project", &["Global/SublimeText"]),
+    ("*.xcodeproj", &["Global/Xcode"]),
+    (".project", &["Global/Eclipse"]),
+    ("nbproject", &["Global/NetBeans"]),
+];
+
+#[derive(Debug, Default)]
+pub struct Detection {
+    pub languages: Vec<String>,
+    pub editors: Vec<String>,
+    pub os: Vec<String>,
+}
+
+/// Suggests catalog groups for the project in `dir` and the machine d-ig runs on.
+pub fn detect(dir: &Path) -> Detection {
+    let names = entries(dir);
+
+    let mut editors = matching(&names, EDITOR_MARKERS);
+
+    if let Ok(editor) = env::var("VISUAL").or_else(|_| env::var("EDITOR")) {
+        let group = match editor.rsplit('/').next().unwrap_or("") {
+            "vim" | "nvim" | "vi" => Some("Global/Vim"),
+            "emacs" | "emacsclient" => Some("Global/Emacs"),
+            "code" => Some("Global/VisualStudioCode"),
+            "subl" => Some("Global/SublimeText"),
+            _ => None,
+        };
+
+        if let Some(group) = group {
+            if !editors.iter().any(|x| x == group) {
+                editors.push(group.to_string());
+            }
+        }
+    }
+
+    let os = match env::consts::OS {
+        "macos" => vec!["Global/macOS".to_string()],
+        "linux" => vec!["Global/Linux".to_string()],
+        "windows" => vec!["Global/Windows".to_string()],
+        _ => Vec::new(),
+    };
+
+    return Detection {
+        languages: matching(&names, LANGUAGE_MARKERS),
+        editors,
+        os,
+    };
+}
+
+/// Catalog groups suggested by `markers` for a directory containing `names`.
+pub fn matching(names: &[String], markers: &[(&str, &[&str])]) -> Vec<String> {
+    let mut groups = Vec::new();
+
+    for (marker, marker_groups) in markers {
+        let found = match marker.strip_prefix('*') {
+            Some(extension) => names.iter().any(|x| x.ends_with(extension)),
+            None => names.iter().any(|x| x == marker),
+        };
+
+        if !found {
+            continue;
+        }
+
+        for group in marker_groups.iter() {
+            if !groups.iter().any(|x| x == group) {
+                groups.push(group.to_string());
+            }
+        }
+    }
+
+    return groups;
+}
+
+/// Names of the files and directories directly inside `dir`.
+pub fn entries(dir: &Path) -> Vec<String> {
+    return match read_dir(dir) {
+        Ok(dir) => dir
+            .flatten()
+            .map(|x| x.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+}
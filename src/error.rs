@@ -0,0 +1,150 @@
+use crate::{output::paint_err, r#const::RED};
+use std::{fmt::Display, io};
+
+#[derive(Debug)]
+pub enum Error {
+    /// A group, section, file or previous version that does not exist.
+    NotFound(String),
+    /// A group or file that already exists.
+    AlreadyPresent(String),
+    /// Reading, writing or running something failed.
+    Io(String, io::Error),
+    /// A file on disk could not be understood.
+    Parse(String),
+    /// The command line was invalid.
+    Usage(String),
+    /// The embedded ignore group catalog is broken.
+    Catalog(String),
+    /// The file does not match what was required of it.
+    Drift(String),
+    /// Every problem found while planning changes.
+    Many(Vec<Error>),
+}
+
+impl Error {
+    /// Process exit code for the error kind.
+    ///
+    /// | Code | Kind |
+    /// |------|------|
+    /// | 1    | several problems of different kinds |
+    /// | 2    | usage |
+    /// | 3    | not found |
+    /// | 4    | already present |
+    /// | 5    | io |
+    /// | 6    | parse |
+    /// | 7    | catalog |
+    /// | 8    | drift |
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => 2,
+            Error::NotFound(_) => 3,
+            Error::AlreadyPresent(_) => 4,
+            Error::Io(_, _) => 5,
+            Error::Parse(_) => 6,
+            Error::Catalog(_) => 7,
+            Error::Drift(_) => 8,
+            Error::Many(errors) => {
+                let mut codes = errors.iter().map(|x| x.code());
+
+                match codes.next() {
+                    Some(first) if codes.all(|x| x == first) => first,
+                    _ => 1,
+                }
+            }
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::AlreadyPresent(_) => "already_present",
+            Error::Io(_, _) => "io",
+            Error::Parse(_) => "parse",
+            Error::Usage(_) => "usage",
+            Error::Catalog(_) => "catalog",
+            Error::Drift(_) => "drift",
+            Error::Many(_) => "many",
+        }
+    }
+
+    pub fn log(&self) {
+        eprintln!("{}", paint_err(RED, format!("ERROR: {self}")))
+    }
+
+    /// Single line JSON description of the error for scripts.
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"kind\":\"{}\",\"code\":{},\"message\":{}",
+            self.kind(),
+            self.code(),
+            json_string(&self.to_string())
+        );
+
+        match self {
+            Error::Io(_, cause) => {
+                json += &format!(",\"cause\":{}", json_string(&cause.to_string()))
+            }
+            Error::Many(errors) => {
+                let errors: Vec<String> = errors.iter().map(|x| x.to_json()).collect();
+                json += &format!(",\"errors\":[{}]", errors.join(","));
+            }
+            _ => {}
+        }
+
+        json += "}";
+
+        return json;
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(msg)
+            | Error::AlreadyPresent(msg)
+            | Error::Parse(msg)
+            | Error::Usage(msg)
+            | Error::Catalog(msg)
+            | Error::Drift(msg) => write!(f, "{msg}"),
+            Error::Io(msg, cause) => write!(f, "{msg}: {cause}"),
+            Error::Many(errors) => {
+                write!(f, "{} problem(s) found, nothing was written:", errors.len())?;
+
+                for err in errors {
+                    write!(f, "\n    {err}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, cause) => Some(cause),
+            _ => None,
+        }
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::from("\"");
+
+    for c in string.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            '\t' => json += "\\t",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+
+    return json;
+}
@@ -0,0 +1,130 @@
+use crate::{
+    gitignore::{cwd, GitIgnore, Rule},
+    output::paint,
+    pattern::{check, check_docker, Pattern},
+    r#const::{BLUE, GREEN, YELLOW},
+    target::Target,
+    Error,
+};
+use std::path::{Component, Path, PathBuf};
+
+pub fn explain(paths: &[String], target: Target) -> Result<(), Error> {
+    let ignore_path = GitIgnore::path(target)?;
+    let root = match ignore_path.parent() {
+        Some(root) => root.to_path_buf(),
+        None => {
+            return Err(Error::NotFound(format!(
+                "Could not find directory of {}",
+                target.file_name()
+            )))
+        }
+    };
+
+    let cwd = cwd()?;
+
+    let rules = GitIgnore::rules(&ignore_path)?;
+    let patterns: Vec<Pattern> = rules.iter().map(|rule| rule.pattern.clone()).collect();
+
+    for path in paths {
+        let relative = match relative_to(&root, &normalize(&cwd.join(path))) {
+            Some(relative) => relative,
+            None => {
+                println!("{}: outside of {}\n", paint(YELLOW, path), root.display());
+                continue;
+            }
+        };
+
+        let is_dir = root.join(&relative).is_dir();
+        let verdict = match target {
+            Target::Gitignore => check(&patterns, &relative, is_dir),
+            Target::Dockerignore => check_docker(&patterns, &relative, is_dir),
+        };
+
+        let decider = match verdict.decider {
+            Some(decider) => &rules[decider],
+            None => {
+                println!("{}: not ignored\n", paint(GREEN, path));
+                continue;
+            }
+        };
+
+        match verdict.ignored {
+            true => println!("{}: ignored", paint(YELLOW, path)),
+            false => println!("{}: not ignored (re-included)", paint(GREEN, path)),
+        }
+
+        print_rule("decided by", decider, target);
+
+        let others: Vec<&Rule> = verdict
+            .matched
+            .iter()
+            .map(|idx| &rules[*idx])
+            .filter(|rule| rule.line != decider.line)
+            .collect();
+
+        if !others.is_empty() {
+            println!("    also matched:");
+            for rule in others {
+                println!(
+                    "        {}:{}: {} [{}]",
+                    target.file_name(),
+                    rule.line,
+                    paint(BLUE, &rule.pattern.source),
+                    section_label(rule)
+                );
+            }
+        }
+
+        println!();
+    }
+
+    return Ok(());
+}
+
+fn print_rule(label: &str, rule: &Rule, target: Target) {
+    println!(
+        "    {label} {}:{}: {} [{}]",
+        target.file_name(),
+        rule.line,
+        paint(BLUE, &rule.pattern.source),
+        section_label(rule)
+    );
+
+    for comment in &rule.comments {
+        println!("        {}", paint(YELLOW, comment));
+    }
+}
+
+fn section_label(rule: &Rule) -> &str {
+    match &rule.section {
+        Some(section) => section,
+        None => "no section",
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            _ => normal.push(component),
+        }
+    }
+
+    return normal;
+}
+
+fn relative_to(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(normalize(root)).ok()?;
+
+    let parts: Vec<String> = relative
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    return Some(parts.join("/"));
+}
@@ -0,0 +1,487 @@
+use crate::{
+    gitignore::GitIgnore,
+    header, history,
+    output::paint_err,
+    pattern::{check, Pattern},
+    r#const::{EXPORT, FORCE, TARGET, TO, UNMANAGED, YELLOW},
+    target::Target,
+    Error,
+};
+use std::fs::read_to_string;
+
+/// First line of every exported file, which tells an earlier export from a hand-written file.
+const GENERATED: &str = "# Generated by d-ig";
+
+/// Ignore files that can be generated from the managed sections of a .gitignore.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Dockerignore,
+    Npmignore,
+    Prettierignore,
+    Eslintignore,
+    Hgignore,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Result<Format, Error> {
+        match value {
+            "dockerignore" => Ok(Format::Dockerignore),
+            "npmignore" => Ok(Format::Npmignore),
+            "prettierignore" => Ok(Format::Prettierignore),
+            "eslintignore" => Ok(Format::Eslintignore),
+            "hgignore" => Ok(Format::Hgignore),
+            _ => Err(Error::Usage(format!(
+                "Invalid {TO} format \"{value}\". Expected dockerignore, npmignore, prettierignore, eslintignore or hgignore."
+            ))),
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Format::Dockerignore => ".dockerignore",
+            Format::Npmignore => ".npmignore",
+            Format::Prettierignore => ".prettierignore",
+            Format::Eslintignore => ".eslintignore",
+            Format::Hgignore => ".hgignore",
+        }
+    }
+
+    /// Rewrites a single rule of a `source` file for the format, with a warning if its
+    /// meaning changes.
+    fn translate(self, pattern: &Pattern, source: Target) -> (String, Option<String>) {
+        match (self, source) {
+            // npm, prettier and eslint all read gitignore syntax. Mercurial rules depend on
+            // the negations after them, so they are translated together by `translate_hg`.
+            (
+                Format::Npmignore
+                | Format::Prettierignore
+                | Format::Eslintignore
+                | Format::Hgignore,
+                Target::Gitignore,
+            ) => return (pattern.source.clone(), None),
+            // Docker matches every rule from the root of the build context, which a leading
+            // `/` says in gitignore syntax.
+            (
+                Format::Npmignore
+                | Format::Prettierignore
+                | Format::Eslintignore
+                | Format::Hgignore,
+                Target::Dockerignore,
+            ) => {
+                let negation = match pattern.negated {
+                    true => "!",
+                    false => "",
+                };
+
+                return (format!("{negation}/{}", pattern.glob()), None);
+            }
+            (Format::Dockerignore, _) => {}
+        }
+
+        // Docker matches every pattern from the root of the build context with Go's
+        // filepath.Match, which spells negated character classes `[^...]`.
+        let glob = pattern.glob().replace("[!", "[^");
+
+        let mut rule = match pattern.anchored {
+            true => glob,
+            false => format!("**/{glob}"),
+        };
+
+        if rule.starts_with(['#', '!']) {
+            rule.insert(0, '\\');
+        }
+
+        let warning = match (pattern.negated, pattern.dir_only) {
+            (true, _) => Some(format!(
+                "'{}' can re-include files inside excluded directories, which git never does.",
+                pattern.source
+            )),
+            (false, true) => Some(format!(
+                "'{}' also matches files, {} can't limit a rule to directories.",
+                pattern.source,
+                self.file_name()
+            )),
+            (false, false) => None,
+        };
+
+        if pattern.negated {
+            rule.insert(0, '!');
+        }
+
+        return (rule, warning);
+    }
+}
+
+/// The managed rules of a .gitignore translated to another format.
+pub struct Export {
+    pub content: String,
+    pub warnings: Vec<String>,
+}
+
+/// Translates every rule in the managed sections of `gitignore` to `format`.
+pub fn translate(gitignore: &GitIgnore, format: Format) -> Export {
+    // Later rules override earlier ones, so sections stay in file order.
+    let sections = gitignore.sections();
+
+    let mut content = format!(
+        "{GENERATED} from the managed sections of {}, do not edit.\n",
+        gitignore.target().file_name()
+    );
+    let mut warnings = Vec::new();
+
+    let sections: Vec<(String, Vec<Pattern>)> = sections
+        .into_iter()
+        .map(|x| {
+            let patterns = gitignore.section_patterns(&x);
+            (x, patterns)
+        })
+        .filter(|(_, patterns)| !patterns.is_empty())
+        .collect();
+
+    match format {
+        Format::Hgignore => {
+            translate_hg(&sections, gitignore.target(), &mut content, &mut warnings)
+        }
+        _ => {
+            for (section, patterns) in &sections {
+                content += &format!("\n# {section}\n");
+
+                for pattern in patterns {
+                    let (rule, warning) = format.translate(pattern, gitignore.target());
+
+                    content += &rule;
+                    content += "\n";
+                    warnings.extend(warning);
+                }
+            }
+        }
+    }
+
+    let unmanaged = gitignore.section_patterns(UNMANAGED).len();
+
+    if unmanaged > 0 {
+        warnings.push(format!(
+            "{unmanaged} rule(s) outside of d-ig sections were not exported."
+        ));
+    }
+
+    return Export { content, warnings };
+}
+
+/// Writes Mercurial rules for `sections`. Plain rules that match at any depth stay globs.
+/// Anchored and directory only rules become regular expressions, and since Mercurial has
+/// no negation, each later negation that re-includes part of a rule becomes a lookahead
+/// on it. Negations git would not apply, and ones that re-include nothing, are left out.
+fn translate_hg(
+    sections: &[(String, Vec<Pattern>)],
+    source: Target,
+    content: &mut String,
+    warnings: &mut Vec<String>,
+) {
+    let patterns: Vec<&Pattern> = sections.iter().flat_map(|(_, x)| x).collect();
+    let excluded: Vec<bool> = patterns
+        .iter()
+        .enumerate()
+        .map(|(idx, x)| {
+            // Unlike git, Docker re-includes files from inside excluded directories.
+            x.negated && source == Target::Gitignore && parent_excluded(&patterns[..idx], x)
+        })
+        .collect();
+    let mut used = vec![false; patterns.len()];
+    let mut syntax = "";
+    let mut idx = 0;
+
+    for (section, section_patterns) in sections {
+        *content += &format!("\n# {section}\n");
+
+        for pattern in section_patterns {
+            let current = idx;
+            idx += 1;
+
+            if pattern.negated {
+                let warning = match (excluded[current], used[current]) {
+                    (true, _) => "is inside a directory an earlier rule excludes, so git never re-includes it. It was left out of .hgignore.",
+                    (false, true) => "became a lookahead on the earlier rules it re-includes from, since .hgignore has no negation.",
+                    (false, false) => "re-includes nothing an earlier rule ignores. It was left out of .hgignore.",
+                };
+
+                warnings.push(format!("'{}' {warning}", pattern.source));
+                continue;
+            }
+
+            let negations: Vec<usize> = (current + 1..patterns.len())
+                .filter(|x| patterns[*x].negated && !excluded[*x])
+                .filter(|x| overlaps(pattern, patterns[*x]))
+                .collect();
+
+            for negation in &negations {
+                used[*negation] = true;
+            }
+
+            let (rule_syntax, rule) =
+                match pattern.anchored || pattern.dir_only || !negations.is_empty() {
+                    false => ("glob", pattern.glob()),
+                    true => {
+                        let lookaheads: String = negations
+                            .iter()
+                            .map(|x| format!("(?!{})", hg_regex(patterns[*x], false)))
+                            .collect();
+
+                        (
+                            "regexp",
+                            format!("^{lookaheads}{}", hg_regex(pattern, pattern.dir_only)),
+                        )
+                    }
+                };
+
+            if rule_syntax != syntax {
+                syntax = rule_syntax;
+                *content += &format!("syntax: {syntax}\n");
+            }
+
+            *content += &rule;
+            *content += "\n";
+        }
+    }
+}
+
+/// Whether one of the `earlier` rules excludes a directory above the path `negation`
+/// names, from where git does not re-include files.
+fn parent_excluded(earlier: &[&Pattern], negation: &Pattern) -> bool {
+    let earlier: Vec<Pattern> = earlier.iter().map(|x| (*x).clone()).collect();
+    let glob = negation.glob();
+    let parts: Vec<&str> = glob.split('/').collect();
+
+    return (1..parts.len()).any(|end| check(&earlier, &parts[..end].join("/"), true).ignored);
+}
+
+/// Whether `negation` could re-include something `rule` ignores: the rule matches the full
+/// path the negation names, taking wildcards in the negation as literal characters, or
+/// the negation matches the rule's own glob.
+fn overlaps(rule: &Pattern, negation: &Pattern) -> bool {
+    return rule.matches(&negation.glob(), true) || negation.matches(&rule.glob(), true);
+}
+
+/// A regular expression matching from the start of a path whatever `pattern` ignores,
+/// including everything inside a matched directory. With `dir_only` something has to
+/// follow the match, so it can't match a file. Lookaheads leave it off, so a negated
+/// directory is not matched itself either.
+fn hg_regex(pattern: &Pattern, dir_only: bool) -> String {
+    let prefix = match pattern.anchored {
+        true => "",
+        false => "(?:.*/)?",
+    };
+
+    let suffix = match dir_only {
+        true => "/",
+        false => "(?:/|$)",
+    };
+
+    return format!("{prefix}{}{suffix}", glob_regex(&pattern.glob()));
+}
+
+/// Converts a gitignore glob into a regular expression.
+fn glob_regex(glob: &str) -> String {
+    let glob: Vec<char> = glob.chars().collect();
+    let mut regex = String::new();
+    let mut idx = 0;
+
+    while idx < glob.len() {
+        match glob[idx] {
+            '*' if glob.get(idx + 1) == Some(&'*') => {
+                match glob.get(idx + 2) {
+                    Some('/') => {
+                        regex += "(?:.*/)?";
+                        idx += 1;
+                    }
+                    _ => regex += ".*",
+                }
+
+                idx += 1;
+            }
+            '*' => regex += "[^/]*",
+            '?' => regex += "[^/]",
+            '[' => match glob[idx + 1..].iter().skip(1).position(|x| *x == ']') {
+                Some(len) => {
+                    let class: String = glob[idx + 1..idx + len + 2].iter().collect();
+                    let class = class
+                        .strip_prefix('!')
+                        .map_or(class.clone(), |x| format!("^{x}"));
+
+                    regex += &format!("[{class}]");
+                    idx += len + 2;
+                }
+                None => regex += "\\[",
+            },
+            '\\' if idx + 1 < glob.len() => {
+                idx += 1;
+                regex += &escape(glob[idx]);
+            }
+            c => regex += &escape(c),
+        }
+
+        idx += 1;
+    }
+
+    return regex;
+}
+
+fn escape(c: char) -> String {
+    match ".^$+(){}|[]*?\\".contains(c) {
+        true => format!("\\{c}"),
+        false => c.to_string(),
+    }
+}
+
+/// Writes the managed rules of the `target` file to `format`'s file next to it, or prints
+/// them when `stdout` is set. A file that an earlier export did not write is only
+/// replaced with `force`.
+pub fn export(format: Format, stdout: bool, force: bool, target: Target) -> Result<(), Error> {
+    let gitignore = GitIgnore::load(false, target)?;
+    let export = translate(&gitignore, format);
+
+    for warning in &export.warnings {
+        eprintln!("{}", paint_err(YELLOW, format!("WARNING: {warning}")));
+    }
+
+    if stdout {
+        print!("{}", export.content);
+        return Ok(());
+    }
+
+    let path = gitignore.file().with_file_name(format.file_name());
+
+    // A .dockerignore d-ig manages has its own sections, which an export would wipe out.
+    if format == Format::Dockerignore
+        && path.is_file()
+        && !GitIgnore::open(&path)?.sections().is_empty()
+    {
+        return Err(Error::AlreadyPresent(format!(
+            "{} has d-ig sections of its own. Manage it with {TARGET}=dockerignore instead.",
+            path.display()
+        )));
+    }
+
+    if path.is_file() && !force {
+        let existing = match read_to_string(&path) {
+            Ok(existing) => existing,
+            Err(err) => return Err(Error::Io(format!("Could not read {}", path.display()), err)),
+        };
+
+        let generated = existing
+            .lines()
+            .next()
+            .is_some_and(|x| x.starts_with(GENERATED));
+        let has_sections = existing.lines().any(|x| header::read(x).is_some());
+
+        if !existing.trim().is_empty() && (has_sections || !generated) {
+            return Err(Error::AlreadyPresent(format!(
+                "{} was not written by {EXPORT}. Use {FORCE} to replace it.",
+                path.display()
+            )));
+        }
+    }
+
+    history::save(&path, &export.content)?;
+
+    println!("Wrote {}.", path.display());
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(line: &str) -> Pattern {
+        return Pattern::parse(line).unwrap();
+    }
+
+    #[test]
+    fn glob_regex_converts_wildcards() {
+        assert_eq!(glob_regex("*.log"), r"[^/]*\.log");
+        assert_eq!(glob_regex("file?.[!ab]"), r"file[^/]\.[^ab]");
+        assert_eq!(glob_regex("**/foo"), "(?:.*/)?foo");
+        assert_eq!(glob_regex("a/**/b"), "a/(?:.*/)?b");
+        assert_eq!(glob_regex("a/**"), "a/.*");
+        assert_eq!(glob_regex(r"a\*b(1)"), r"a\*b\(1\)");
+        assert_eq!(glob_regex("[abc"), r"\[abc");
+    }
+
+    #[test]
+    fn hg_regex_anchors_and_limits_to_directories() {
+        assert_eq!(
+            hg_regex(&pattern("*.log"), false),
+            r"(?:.*/)?[^/]*\.log(?:/|$)"
+        );
+        assert_eq!(hg_regex(&pattern("/build"), false), "build(?:/|$)");
+        assert_eq!(hg_regex(&pattern("logs/"), true), "(?:.*/)?logs/");
+
+        // As a lookahead a negated directory must not leave the directory itself matched.
+        assert_eq!(hg_regex(&pattern("!logs/keep/"), false), "logs/keep(?:/|$)");
+    }
+
+    #[test]
+    fn overlaps_needs_the_rule_to_match_the_negated_path() {
+        assert!(overlaps(&pattern("*.log"), &pattern("!keep.log")));
+        assert!(overlaps(&pattern("logs/*"), &pattern("!logs/keep/")));
+        assert!(overlaps(&pattern("debug.log"), &pattern("!*.log")));
+
+        // Only excluding a directory above the negated path is not an overlap.
+        assert!(!overlaps(&pattern("logs/"), &pattern("!logs/keep.txt")));
+        assert!(!overlaps(&pattern("target/"), &pattern("!keep.log")));
+    }
+
+    #[test]
+    fn dockerignore_rules_are_rooted_in_gitignore_syntax() {
+        let rule = Pattern::parse_docker("**/*.log").unwrap();
+        let negation = Pattern::parse_docker("!build/keep").unwrap();
+
+        for format in [
+            Format::Npmignore,
+            Format::Prettierignore,
+            Format::Eslintignore,
+        ] {
+            assert_eq!(format.translate(&rule, Target::Dockerignore).0, "/**/*.log");
+            assert_eq!(
+                format.translate(&negation, Target::Dockerignore).0,
+                "!/build/keep"
+            );
+            assert_eq!(
+                format.translate(&pattern("*.log"), Target::Gitignore).0,
+                "*.log"
+            );
+        }
+    }
+
+    #[test]
+    fn hg_negations_follow_git() {
+        let sections = vec![
+            (
+                "Logs".to_string(),
+                vec![
+                    pattern("logs/"),
+                    pattern("!logs/keep.txt"),
+                    pattern("*.log"),
+                ],
+            ),
+            (
+                "_".to_string(),
+                vec![pattern("!keep.log"), pattern("!unused")],
+            ),
+        ];
+
+        let mut content = String::new();
+        let mut warnings = Vec::new();
+        translate_hg(&sections, Target::Gitignore, &mut content, &mut warnings);
+
+        assert_eq!(
+            content,
+            "\n# Logs\nsyntax: regexp\n^(?:.*/)?logs/\n^(?!(?:.*/)?keep\\.log(?:/|$))(?:.*/)?[^/]*\\.log(?:/|$)\n\n# _\n"
+        );
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("'!logs/keep.txt' is inside a directory"));
+        assert!(warnings[1].contains("'!keep.log' became a lookahead"));
+        assert!(warnings[2].contains("'!unused' re-includes nothing"));
+    }
+}
@@ -0,0 +1,371 @@
+use crate::{
+    pattern::{check, Pattern},
+    Error,
+};
+use std::{
+    fs::{read, read_to_string},
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub struct Repo {
+    /// Top of the working tree.
+    pub root: PathBuf,
+    pub git_dir: PathBuf,
+}
+
+impl Repo {
+    /// Searches upward from `dir` for the directory containing `.git`.
+    pub fn find(dir: &Path) -> Option<Repo> {
+        let dot_git = dir.join(".git");
+
+        if dot_git.is_dir() {
+            return Some(Repo {
+                root: dir.to_path_buf(),
+                git_dir: dot_git,
+            });
+        }
+
+        // Worktrees and submodules use a `.git` file pointing at the real git directory.
+        if dot_git.is_file() {
+            let content = read_to_string(&dot_git).ok()?;
+            let git_dir = content.trim().strip_prefix("gitdir:")?.trim();
+
+            return Some(Repo {
+                root: dir.to_path_buf(),
+                git_dir: dir.join(git_dir),
+            });
+        }
+
+        return Repo::find(dir.parent()?);
+    }
+
+    /// Lists every path in the index, relative to the top of the working tree.
+    pub fn tracked_files(&self) -> Result<Vec<String>, Error> {
+        let path = self.git_dir.join("index");
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let data = match read(&path) {
+            Ok(data) => data,
+            Err(err) => return Err(Error::Io("Could not read git index".to_string(), err)),
+        };
+
+        return parse_index(&data, self.hash_size());
+    }
+
+    /// Lists tracked files, relative to the top of the working tree, that `patterns`
+    /// from the .gitignore in `ignore_dir` would ignore.
+    pub fn tracked_matches(
+        &self,
+        ignore_dir: &Path,
+        patterns: &[Pattern],
+    ) -> Result<Vec<String>, Error> {
+        if patterns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let prefix = match ignore_dir.strip_prefix(&self.root) {
+            Ok(prefix) => prefix.to_string_lossy().replace('\\', "/"),
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let mut matches = Vec::new();
+
+        for file in self.tracked_files()? {
+            let relative = match prefix.is_empty() {
+                true => file.as_str(),
+                false => match file.strip_prefix(&format!("{prefix}/")) {
+                    Some(relative) => relative,
+                    None => continue,
+                },
+            };
+
+            if check(patterns, relative, false).ignored {
+                matches.push(file);
+            }
+        }
+
+        return Ok(matches);
+    }
+
+    /// Removes `files` from the index while leaving them in the working tree.
+    pub fn untrack(&self, files: &[String]) -> Result<(), Error> {
+        let status = Command::new("git")
+            .args(["rm", "--cached", "--quiet", "--"])
+            .args(files)
+            .current_dir(&self.root)
+            .status();
+
+        return match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(Error::Io(
+                "git rm --cached failed".to_string(),
+                io::Error::other(status.to_string()),
+            )),
+            Err(err) => Err(Error::Io("Could not run git rm --cached".to_string(), err)),
+        };
+    }
+
+    fn hash_size(&self) -> usize {
+        let config = read_to_string(self.git_dir.join("config")).unwrap_or_default();
+
+        for line in config.lines() {
+            let line = line.replace(' ', "");
+            if line == "objectformat=sha256" {
+                return 32;
+            }
+        }
+
+        return 20;
+    }
+}
+
+fn parse_index(data: &[u8], hash_size: usize) -> Result<Vec<String>, Error> {
+    let corrupt = || Error::Parse("Could not parse git index: file is corrupted.".to_string());
+
+    if data.len() < 12 || &data[..4] != b"DIRC" {
+        return Err(corrupt());
+    }
+
+    let version = read_u32(data, 4).ok_or_else(corrupt)?;
+    let count = read_u32(data, 8).ok_or_else(corrupt)? as usize;
+
+    if !(2..=4).contains(&version) {
+        return Err(Error::Parse(format!(
+            "Could not parse git index: unsupported version {version}."
+        )));
+    }
+
+    // The count comes from the file, so only trust it as far as the data could hold entries.
+    let mut files = Vec::with_capacity(count.min(data.len() / (40 + hash_size + 2)));
+    let mut previous: Vec<u8> = Vec::new();
+    let mut offset = 12;
+
+    for _ in 0..count {
+        let start = offset;
+
+        // ctime, mtime, dev, ino, mode, uid, gid and size are 40 bytes, followed by the object hash.
+        offset += 40 + hash_size;
+
+        let flags = read_u16(data, offset).ok_or_else(corrupt)?;
+        offset += 2;
+
+        if version >= 3 && flags & 0x4000 != 0 {
+            offset += 2;
+        }
+
+        let name = match version {
+            4 => {
+                let (strip, len) =
+                    read_varint(data.get(offset..).ok_or_else(corrupt)?).ok_or_else(corrupt)?;
+                offset += len;
+
+                let suffix_len = nul_position(data, offset).ok_or_else(corrupt)?;
+                let keep = previous.len().checked_sub(strip).ok_or_else(corrupt)?;
+
+                let mut name = previous[..keep].to_vec();
+                name.extend_from_slice(&data[offset..offset + suffix_len]);
+                offset += suffix_len + 1;
+
+                name
+            }
+            _ => {
+                let name_len = nul_position(data, offset).ok_or_else(corrupt)?;
+                let name = data[offset..offset + name_len].to_vec();
+
+                // Entries are NUL padded to a multiple of eight bytes.
+                let entry_len = offset + name_len - start;
+                offset = start + (entry_len + 8) / 8 * 8;
+
+                name
+            }
+        };
+
+        files.push(String::from_utf8_lossy(&name).to_string());
+        previous = name;
+    }
+
+    return Ok(files);
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    return Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    return Some(u16::from_be_bytes([bytes[0], bytes[1]]));
+}
+
+fn nul_position(data: &[u8], offset: usize) -> Option<usize> {
+    return data.get(offset..)?.iter().position(|x| *x == 0);
+}
+
+/// Reads git's offset varint, returning the value and the number of bytes used.
+fn read_varint(data: &[u8]) -> Option<(usize, usize)> {
+    let mut idx = 0;
+    let mut byte = *data.first()?;
+    let mut value = (byte & 0x7f) as usize;
+
+    while byte & 0x80 != 0 {
+        idx += 1;
+        byte = *data.get(idx)?;
+        value = value.checked_add(1)?.checked_mul(0x80)? | (byte & 0x7f) as usize;
+    }
+
+    return Some((value, idx + 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: usize = 20;
+
+    fn header(version: u32, count: u32) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&version.to_be_bytes());
+        data.extend_from_slice(&count.to_be_bytes());
+        return data;
+    }
+
+    /// Stat data and object hash, then the flags holding the name length.
+    fn entry_start(data: &mut Vec<u8>, name_len: usize, extended: bool) {
+        data.extend_from_slice(&[0; 40 + HASH]);
+
+        let mut flags = name_len.min(0xfff) as u16;
+        if extended {
+            flags |= 0x4000;
+        }
+
+        data.extend_from_slice(&flags.to_be_bytes());
+
+        if extended {
+            // skip-worktree and intent-to-add live here.
+            data.extend_from_slice(&0x6000u16.to_be_bytes());
+        }
+    }
+
+    /// A version 2 or 3 index, with every entry NUL padded to a multiple of eight bytes.
+    fn index(version: u32, entries: &[(&str, bool)]) -> Vec<u8> {
+        let mut data = header(version, entries.len() as u32);
+
+        for (name, extended) in entries {
+            let start = data.len();
+            entry_start(&mut data, name.len(), *extended);
+            data.extend_from_slice(name.as_bytes());
+
+            let len = data.len() - start;
+            data.resize(start + (len + 8) / 8 * 8, 0);
+        }
+
+        data.extend_from_slice(&[0xab; HASH]);
+        return data;
+    }
+
+    /// A version 4 index from entries of how many bytes to strip from the previous name
+    /// and what to append to it.
+    fn index_v4(entries: &[(u8, &str)]) -> Vec<u8> {
+        let mut data = header(4, entries.len() as u32);
+
+        for (strip, suffix) in entries {
+            entry_start(&mut data, 0, false);
+            data.push(*strip);
+            data.extend_from_slice(suffix.as_bytes());
+            data.push(0);
+        }
+
+        data.extend_from_slice(&[0xab; HASH]);
+        return data;
+    }
+
+    #[test]
+    fn v2_skips_padding() {
+        // Names of 1, 2 and 8 bytes end at different places in their padding.
+        let names = ["a", "bc", "dir/file", "longer/path/name.rs"];
+        let entries: Vec<(&str, bool)> = names.iter().map(|x| (*x, false)).collect();
+
+        assert_eq!(parse_index(&index(2, &entries), HASH).unwrap(), names);
+    }
+
+    #[test]
+    fn v3_skips_extended_flags() {
+        let data = index(3, &[("a.txt", true), ("b.txt", false), ("c/d.txt", true)]);
+
+        assert_eq!(
+            parse_index(&data, HASH).unwrap(),
+            ["a.txt", "b.txt", "c/d.txt"]
+        );
+    }
+
+    #[test]
+    fn v4_expands_prefix_compression() {
+        let data = index_v4(&[(0, "src/a.rs"), (4, "b.rs"), (8, "tests/c.rs"), (4, "d.rs")]);
+
+        assert_eq!(
+            parse_index(&data, HASH).unwrap(),
+            ["src/a.rs", "src/b.rs", "tests/c.rs", "tests/d.rs"]
+        );
+    }
+
+    #[test]
+    fn reads_multi_byte_varints() {
+        assert_eq!(read_varint(&[0x7f]), Some((127, 1)));
+        assert_eq!(read_varint(&[0x80, 0x00]), Some((128, 2)));
+        assert_eq!(read_varint(&[0xff, 0x7f, 0x55]), Some((16511, 2)));
+        assert_eq!(read_varint(&[0x80]), None);
+        assert_eq!(read_varint(&[0xff; 16]), None);
+    }
+
+    #[test]
+    fn truncated_index_is_a_parse_error() {
+        let v2 = index(2, &[("a.txt", false), ("dir/b.txt", false)]);
+        let v4 = index_v4(&[(0, "dir/a.txt"), (5, "b.txt")]);
+
+        for data in [v2, v4] {
+            // Everything but the trailing hash is needed to read the entries.
+            for len in 0..data.len() - HASH {
+                match parse_index(&data[..len], HASH) {
+                    Err(Error::Parse(_)) => {}
+                    other => panic!(
+                        "{len} bytes parsed as {:?}",
+                        other.map_err(|x| x.to_string())
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_index_is_a_parse_error() {
+        let mut bad_signature = index(2, &[("a", false)]);
+        bad_signature[0] = b'X';
+
+        let mut bad_version = index(2, &[("a", false)]);
+        bad_version[7] = 5;
+
+        let mut huge_count = index(2, &[("a", false)]);
+        huge_count[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let mut bad_varint = index_v4(&[(0, "a")]);
+        let varint = 12 + 40 + HASH + 2;
+        bad_varint.splice(varint..varint + 1, [0xff; 16]);
+
+        let strip_too_long = index_v4(&[(0, "a"), (5, "b")]);
+
+        for data in [
+            bad_signature,
+            bad_version,
+            huge_count,
+            bad_varint,
+            strip_too_long,
+        ] {
+            assert!(matches!(parse_index(&data, HASH), Err(Error::Parse(_))));
+        }
+    }
+}
@@ -1,156 +1,604 @@
 use crate::{
+    header::{self, HeaderStyle},
+    history,
     ignore_groups::{IgnoreGroup, IgnoreGroups, IgnoreItem},
-    r#const::{CLEAR, CLEAR_S, GIT_IGNORE, TITLE_WRAPPER_CLOSE, TITLE_WRAPPER_OPEN},
+    lock::Lock,
+    pattern::Pattern,
+    r#const::{META_PREFIX, META_VERSION, ON_EXISTS, PROGRAM_VERSION, UNMANAGED},
+    target::Target,
     Error,
 };
 use std::{
     collections::HashMap,
     env::current_dir,
-    fs::File,
-    io::{BufRead, BufReader, Write},
-    path::PathBuf,
+    fs::read_to_string,
+    io::{self, Write},
+    path::{Path, PathBuf},
 };
 
-pub struct GitIgnore(HashMap<String, String>);
+/// What `--create` does when a .gitignore already exists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnExists {
+    /// Keeps the existing content as the unmanaged section and adds to it.
+    Merge,
+    /// Starts over with an empty file.
+    Replace,
+    Fail,
+}
+
+impl OnExists {
+    pub fn parse(value: &str) -> Result<OnExists, Error> {
+        match value {
+            "merge" => Ok(OnExists::Merge),
+            "replace" => Ok(OnExists::Replace),
+            "fail" => Ok(OnExists::Fail),
+            _ => Err(Error::Usage(format!(
+                "Invalid {ON_EXISTS} choice \"{value}\". Expected merge, replace or fail."
+            ))),
+        }
+    }
+}
+
+/// How a section was generated, written on the line after its header. Headers from
+/// before this existed have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionMeta {
+    /// Version of the metadata line format.
+    pub version: u32,
+    /// Catalog template the section was generated from.
+    pub source: String,
+    /// d-ig version whose catalog produced the section.
+    pub catalog: String,
+    /// Hash of the section content as generated, see [`content_hash`].
+    pub hash: String,
+    /// Group that pulled this one in as a dependency.
+    pub dependency_of: Option<String>,
+    /// Options the section was generated with, such as `force`.
+    pub options: Vec<String>,
+}
+
+impl SectionMeta {
+    /// Metadata for `content` freshly generated from the catalog group `group`.
+    pub fn new(group: &IgnoreGroup, content: &str) -> SectionMeta {
+        return SectionMeta {
+            version: META_VERSION,
+            source: group.source.clone(),
+            catalog: PROGRAM_VERSION.to_string(),
+            hash: content_hash(content),
+            dependency_of: None,
+            options: Vec::new(),
+        };
+    }
+
+    /// Reads a metadata line. Unknown fields are skipped so newer files still load.
+    pub fn parse(line: &str) -> Option<SectionMeta> {
+        let fields = line.strip_prefix(META_PREFIX)?;
+
+        let mut meta = SectionMeta {
+            version: 0,
+            source: String::new(),
+            catalog: String::new(),
+            hash: String::new(),
+            dependency_of: None,
+            options: Vec::new(),
+        };
+
+        for field in fields.split_whitespace() {
+            let (key, value) = match field.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            match key {
+                "v" => meta.version = value.parse().ok()?,
+                "source" => meta.source = value.to_string(),
+                "catalog" => meta.catalog = value.to_string(),
+                "hash" => meta.hash = value.to_string(),
+                "dependency-of" => meta.dependency_of = Some(value.to_string()),
+                "options" => meta.options = value.split(',').map(|x| x.to_string()).collect(),
+                _ => {}
+            }
+        }
+
+        if meta.version == 0 || meta.hash.is_empty() {
+            return None;
+        }
+
+        return Some(meta);
+    }
+
+    pub fn render(&self) -> String {
+        let mut line = format!(
+            "{META_PREFIX} v={} source={} catalog={} hash={}",
+            self.version, self.source, self.catalog, self.hash
+        );
+
+        if let Some(parent) = &self.dependency_of {
+            line += &format!(" dependency-of={parent}");
+        }
+
+        if !self.options.is_empty() {
+            line += &format!(" options={}", self.options.join(","));
+        }
+
+        return line;
+    }
+}
+
+/// Hash of a section's content, ignoring blank lines around it.
+pub fn content_hash(content: &str) -> String {
+    // 64-bit FNV-1a, which is plenty to notice a section changing.
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in content.trim_matches('\n').bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    return format!("{hash:016x}");
+}
+
+/// The current directory, which commands search upwards from.
+pub(crate) fn cwd() -> Result<PathBuf, Error> {
+    return match current_dir() {
+        Ok(cwd) => Ok(cwd),
+        Err(err) => Err(Error::Io(
+            "Could not get current directory".to_string(),
+            err,
+        )),
+    };
+}
+
+/// A .gitignore split into named sections. Lines before the first section header
+/// belong to the unmanaged section, which d-ig keeps but never edits.
+pub struct GitIgnore {
+    path: PathBuf,
+    /// Told by the file name, decides how rules are read.
+    target: Target,
+    /// Sections in the order they are written, with new groups at the end.
+    sections: Vec<(String, String)>,
+    meta: HashMap<String, SectionMeta>,
+    /// Style the file's headers were read in, kept when writing unless another is set.
+    style: HeaderStyle,
+    /// Read the first time a group is generated or removed.
+    lock: Option<Lock>,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub line: usize,
+    pub section: Option<String>,
+    /// The comment block closest above the rule within its section.
+    pub comments: Vec<String>,
+    pub pattern: Pattern,
+}
 
 impl GitIgnore {
-    pub fn load(new: bool) -> Result<GitIgnore, Error> {
+    /// Loads the `target` file above the current directory, or creates one next to `.git`
+    /// if `new`.
+    pub fn load(new: bool, target: Target) -> Result<GitIgnore, Error> {
         let path = match new {
-            false => GitIgnore::path(),
-            true => GitIgnore::build_path(),
+            false => GitIgnore::path(target),
+            true => GitIgnore::build_path(OnExists::Fail, target),
         }?;
 
-        let file = match File::open(path) {
-            Ok(file) => file,
-            Err(err) => return Err(Error(format!("Could not open {GIT_IGNORE}: {err}"))),
+        return GitIgnore::open(&path);
+    }
+
+    /// An empty .gitignore that will be written to `path`.
+    pub fn new(path: &Path) -> GitIgnore {
+        return GitIgnore {
+            path: path.to_path_buf(),
+            target: Target::of(path),
+            sections: Vec::new(),
+            meta: HashMap::new(),
+            style: HeaderStyle::Banner,
+            lock: None,
         };
+    }
 
-        let reader = BufReader::new(file);
-
-        let mut map = HashMap::new();
-        let mut current_name = String::from("");
-        for line in reader.lines() {
-            let line = match line {
-                Ok(line) => line,
-                Err(err) => {
-                    return Err(Error(format!(
-                        "Could not open read from {GIT_IGNORE}: {err}"
-                    )))
-                }
+    /// Takes over the existing .gitignore at `path` without reading sections from it: all
+    /// of its content becomes the unmanaged section, even lines that look like headers.
+    pub fn merge(path: &Path) -> Result<GitIgnore, Error> {
+        let mut file = GitIgnore::new(path);
+        file.set_section(UNMANAGED, GitIgnore::read(path)?);
+
+        return Ok(file);
+    }
+
+    fn read(path: &Path) -> Result<String, Error> {
+        return match read_to_string(path) {
+            Ok(content) => Ok(content),
+            Err(err) => Err(Error::Io(
+                format!("Could not open {}", Target::of(path).file_name()),
+                err,
+            )),
+        };
+    }
+
+    /// Loads the .gitignore at `path`.
+    pub fn open(path: &Path) -> Result<GitIgnore, Error> {
+        let content = GitIgnore::read(path)?;
+
+        let mut file = GitIgnore::new(path);
+        let mut meta = HashMap::new();
+        let mut style = None;
+
+        // Lines are collected for the current section and only moved into it when the
+        // section ends, so large files don't pay for a lookup on every line.
+        let mut current_name = UNMANAGED.to_string();
+        let mut current = String::new();
+        // Until the first section starts, lines belong to content d-ig did not write.
+        let mut managed = false;
+
+        let mut lines = content.lines().peekable();
+
+        while let Some(line) = lines.next() {
+            let next = lines.peek().copied();
+            let header = match managed {
+                true => header::read_section(line, next),
+                false => header::read_first_section(line, next),
             };
 
-            if line.starts_with(TITLE_WRAPPER_OPEN) && line.ends_with(TITLE_WRAPPER_CLOSE) {
-                let name = line
-                    .strip_prefix(TITLE_WRAPPER_OPEN)
-                    .unwrap()
-                    .strip_suffix(TITLE_WRAPPER_CLOSE)
-                    .unwrap()
-                    .trim();
+            if let Some((name, line_style)) = header {
+                if !current.is_empty() {
+                    file.section_entry(&current_name)
+                        .push_str(&std::mem::take(&mut current));
+                }
 
+                file.set_section(name, String::new());
                 current_name = name.to_string();
-                map.insert(current_name.clone(), String::new());
-            } else {
-                match map.get_mut(&current_name) {
-                    Some(s) => s,
-                    None => return Err(Error(format!("{GIT_IGNORE} has been corrupted. Please run {CLEAR}, {CLEAR_S} to clear and restart."))),
+                managed = true;
+
+                // Only headers d-ig wrote, which are followed by their metadata, tell the
+                // style. A hand-written `### Notes ###` does not.
+                if let Some(section_meta) = next.and_then(SectionMeta::parse) {
+                    lines.next();
+                    style = style.or(line_style);
+                    meta.insert(current_name.clone(), section_meta);
+                }
+
+                continue;
+            }
+
+            // Anything between a closing marker and the next header belongs to no section.
+            if managed && header::is_end(line) {
+                if !current.is_empty() {
+                    file.section_entry(&current_name)
+                        .push_str(&std::mem::take(&mut current));
                 }
-                .push_str(&line);
+
+                current_name = UNMANAGED.to_string();
+                continue;
             }
+
+            current.push_str(line);
+            current.push('\n');
+        }
+
+        if !current.is_empty() {
+            file.section_entry(&current_name).push_str(&current);
         }
 
-        return Ok(GitIgnore(map));
+        for (_, val) in &mut file.sections {
+            let trimmed = val.trim_end_matches('\n').len();
+            val.truncate(trimmed);
+
+            if !val.is_empty() {
+                val.push('\n');
+            }
+        }
+
+        file.meta = meta;
+        file.style = style.unwrap_or(HeaderStyle::Banner);
+
+        return Ok(file);
+    }
+
+    fn section(&self, name: &str) -> Option<&String> {
+        return self
+            .sections
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, x)| x);
+    }
+
+    /// The content of section `name`, added empty at the end if it does not exist.
+    fn section_entry(&mut self, name: &str) -> &mut String {
+        let idx = match self.sections.iter().position(|(x, _)| x == name) {
+            Some(idx) => idx,
+            None => {
+                self.sections.push((name.to_string(), String::new()));
+                self.sections.len() - 1
+            }
+        };
+
+        return &mut self.sections[idx].1;
+    }
+
+    /// Replaces the content of section `name` where it is, or adds it at the end.
+    fn set_section(&mut self, name: &str, content: String) {
+        *self.section_entry(name) = content;
+    }
+
+    pub fn target(&self) -> Target {
+        return self.target;
     }
 
-    pub fn write(self) -> Result<(), Error> {
-        let path = GitIgnore::path()?;
-        let mut content = String::new();
+    /// Writes the headers of every section in `style` instead of the one they were read in.
+    pub fn set_style(&mut self, style: HeaderStyle) {
+        self.style = style;
+    }
+
+    /// Where the file is read from and written to.
+    pub fn file(&self) -> &Path {
+        return &self.path;
+    }
+
+    /// Returns the section name if `line` is a section header.
+    pub fn section_name(line: &str) -> Option<&str> {
+        return header::read(line).map(|(name, _)| name);
+    }
+
+    /// Reads every rule in the .gitignore at `path` along with where it lives in the file.
+    pub fn rules(path: &Path) -> Result<Vec<Rule>, Error> {
+        let content = GitIgnore::read(path)?;
+        let target = Target::of(path);
+
+        let mut rules = Vec::new();
+        let mut section = None;
+        let mut comments = Vec::new();
+        let mut in_comment_block = false;
+
+        let mut managed = false;
 
-        for (key, val) in self.0 {
-            content += &format!("{TITLE_WRAPPER_OPEN} {key} {TITLE_WRAPPER_CLOSE}\n");
-            for line in val.lines() {
-                let val = val.trim();
-                if !val.is_empty() {
-                    content += val;
-                    content += "\n";
+        let mut lines = content.lines().enumerate().peekable();
+
+        while let Some((idx, line)) = lines.next() {
+            let next = lines.peek().map(|(_, x)| *x);
+            let header = match managed {
+                true => header::read_section(line, next),
+                false => header::read_first_section(line, next),
+            };
+
+            if let Some((name, _)) = header {
+                section = Some(name.to_string());
+                comments.clear();
+                in_comment_block = false;
+                managed = true;
+                continue;
+            }
+
+            if managed && header::is_end(line) {
+                section = None;
+                comments.clear();
+                in_comment_block = false;
+                continue;
+            }
+
+            let trimmed = line.trim();
+
+            if trimmed.starts_with(META_PREFIX) {
+                continue;
+            }
+
+            // A blank line ends the comments explaining the rules below it.
+            if trimmed.is_empty() {
+                comments.clear();
+                in_comment_block = false;
+                continue;
+            }
+
+            if trimmed.starts_with('#') {
+                if !in_comment_block {
+                    comments.clear();
+                    in_comment_block = true;
                 }
 
-                content += line;
+                comments.push(trimmed.to_string());
+                continue;
             }
 
-            content += "\n\n"
+            in_comment_block = false;
+
+            if let Some(pattern) = target.pattern(line) {
+                rules.push(Rule {
+                    line: idx + 1,
+                    section: section.clone(),
+                    comments: comments.clone(),
+                    pattern,
+                });
+            }
         }
 
-        let mut file = match File::create(path) {
-            Ok(file) => file,
-            Err(err) => return Err(Error(format!("Could not open {GIT_IGNORE}: {err}"))),
-        };
+        return Ok(rules);
+    }
 
-        return match file.write_all(content.as_bytes()) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(Error(format!("Could not write to {GIT_IGNORE}: {err}"))),
-        };
+    /// Renders the file content without writing it.
+    pub fn render(&self) -> String {
+        let size: usize = self.sections.iter().map(|(_, x)| x.len()).sum();
+        let mut content = Vec::with_capacity(size + self.sections.len() * 256);
+        // Writing to a Vec does not fail.
+        let _ = self.write_to(&mut content);
+
+        return String::from_utf8(content).unwrap_or_default();
     }
 
-    fn build_path() -> Result<PathBuf, Error> {
-        match current_dir() {
-            Ok(path) => GitIgnore::search_for_build_path(path),
-            Err(err) => return Err(Error(format!("Could not get directory directory: {}", err))),
+    /// Streams the file content to `out`, section by section.
+    pub fn write_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        let style = self.style;
+
+        // Content that was not written by d-ig stays at the top, exactly as it was.
+        if let Some(unmanaged) = self.section(UNMANAGED) {
+            let unmanaged = unmanaged.trim_end_matches('\n');
+
+            if !unmanaged.is_empty() {
+                out.write_all(unmanaged.as_bytes())?;
+                out.write_all(b"\n\n")?;
+            }
         }
-    }
 
-    fn search_for_build_path(dir: PathBuf) -> Result<PathBuf, Error> {
-        let git_path = dir.join(".git");
+        for (key, val) in &self.sections {
+            if key == UNMANAGED {
+                continue;
+            }
 
-        if git_path.is_dir() {
-            let file_path = dir.join(GIT_IGNORE);
+            out.write_all(style.header(key).as_bytes())?;
+            out.write_all(b"\n")?;
 
-            if file_path.exists() {
-                return Err(Error(format!(
-                    "{} already exists at {}.",
-                    GIT_IGNORE,
-                    file_path.to_string_lossy()
-                )));
+            if let Some(meta) = self.meta.get(key) {
+                out.write_all(meta.render().as_bytes())?;
+                out.write_all(b"\n")?;
             }
 
-            return match std::fs::File::create(&file_path) {
-                Ok(_) => Ok(file_path),
-                Err(err) => Err(Error(format!("Could not create {}: {}", GIT_IGNORE, err))),
-            };
+            let val = val.trim_matches('\n');
+            if !val.is_empty() {
+                out.write_all(val.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+
+            if let Some(footer) = style.footer(key) {
+                out.write_all(footer.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+
+            out.write_all(b"\n")?;
+        }
+
+        return Ok(());
+    }
+
+    pub fn write(mut self) -> Result<(), Error> {
+        history::save_with(&self.path, |out| self.write_to(out))?;
+
+        return self.lock()?.write();
+    }
+
+    fn lock(&mut self) -> Result<&mut Lock, Error> {
+        if self.lock.is_none() {
+            self.lock = Some(Lock::for_file(&self.path)?);
+        }
+
+        return Ok(self.lock.as_mut().unwrap());
+    }
+
+    fn build_path(on_exists: OnExists, target: Target) -> Result<PathBuf, Error> {
+        return GitIgnore::search_for_build_path(&cwd()?, on_exists, target);
+    }
+
+    /// Starts a new .gitignore next to the `.git` directory, handling an existing file as
+    /// `on_exists` says.
+    pub fn create(on_exists: OnExists, target: Target) -> Result<GitIgnore, Error> {
+        let path = GitIgnore::build_path(on_exists, target)?;
+
+        return match on_exists {
+            OnExists::Replace => Ok(GitIgnore::new(&path)),
+            OnExists::Merge => GitIgnore::merge(&path),
+            OnExists::Fail => GitIgnore::open(&path),
+        };
+    }
+
+    /// Finds where a new .gitignore belongs: next to the `.git` directory at or above `dir`.
+    pub fn build_location(dir: &Path, target: Target) -> Result<PathBuf, Error> {
+        if dir.join(".git").is_dir() {
+            return Ok(dir.join(target.file_name()));
         }
 
         return match dir.parent() {
-            Some(parent) => GitIgnore::search_for_build_path(parent.to_path_buf()),
-            None => Err(Error(format!("Could not find {}", GIT_IGNORE))),
+            Some(parent) => GitIgnore::build_location(parent, target),
+            None => Err(Error::NotFound(format!(
+                "Could not find {}",
+                target.file_name()
+            ))),
         };
     }
 
-    pub fn path() -> Result<PathBuf, Error> {
-        match current_dir() {
-            Ok(path) => GitIgnore::search_for_ignore(path),
-            Err(err) => {
-                return Err(Error(format!(
-                    "Could not get directory getting directory: {}",
-                    err
-                )))
-            }
+    /// Creates an empty .gitignore next to the `.git` directory at or above `dir`. An existing
+    /// file is an error unless `on_exists` is merge or replace, in which case it is left as is.
+    pub fn search_for_build_path(
+        dir: &Path,
+        on_exists: OnExists,
+        target: Target,
+    ) -> Result<PathBuf, Error> {
+        let file_path = GitIgnore::build_location(dir, target)?;
+
+        if file_path.exists() {
+            return match on_exists {
+                OnExists::Merge | OnExists::Replace => Ok(file_path),
+                OnExists::Fail => Err(Error::AlreadyPresent(format!(
+                    "{} already exists at {}. Perhaps use {ON_EXISTS}=merge or {ON_EXISTS}=replace.",
+                    target.file_name(),
+                    file_path.to_string_lossy()
+                ))),
+            };
         }
+
+        return match std::fs::File::create(&file_path) {
+            Ok(_) => Ok(file_path),
+            Err(err) => Err(Error::Io(
+                format!("Could not create {}", target.file_name()),
+                err,
+            )),
+        };
+    }
+
+    pub fn path(target: Target) -> Result<PathBuf, Error> {
+        return GitIgnore::search_for_ignore(&cwd()?, target);
+    }
+
+    /// The closest existing file for `target`, or where a new one would be created.
+    pub fn path_or_build_location(target: Target) -> Result<PathBuf, Error> {
+        return match GitIgnore::path(target) {
+            Ok(path) => Ok(path),
+            Err(_) => GitIgnore::build_location(&cwd()?, target),
+        };
     }
 
-    fn search_for_ignore(dir: PathBuf) -> Result<PathBuf, Error> {
-        let file_path = dir.join(GIT_IGNORE);
+    /// Finds the closest .gitignore at or above `dir`.
+    pub fn search_for_ignore(dir: &Path, target: Target) -> Result<PathBuf, Error> {
+        let file_path = dir.join(target.file_name());
 
         if file_path.is_file() {
             return Ok(file_path);
         }
 
         return match dir.parent() {
-            Some(parent) => GitIgnore::search_for_ignore(parent.to_path_buf()),
-            None => Err(Error(format!("Could not find {}", GIT_IGNORE))),
+            Some(parent) => GitIgnore::search_for_ignore(parent, target),
+            None => Err(Error::NotFound(format!(
+                "Could not find {}",
+                target.file_name()
+            ))),
+        };
+    }
+
+    /// Names of the sections d-ig manages, leaving out the unmanaged content.
+    pub fn sections(&self) -> Vec<String> {
+        return self
+            .sections
+            .iter()
+            .filter(|(x, _)| x != UNMANAGED)
+            .map(|(x, _)| x.clone())
+            .collect();
+    }
+
+    pub fn section_patterns(&self, section: &str) -> Vec<Pattern> {
+        return match self.section(section) {
+            Some(content) => content
+                .lines()
+                .filter_map(|x| self.target.pattern(x))
+                .collect(),
+            None => Vec::new(),
         };
     }
 
-    pub fn has_group(&self, group: &str) -> bool {
-        self.0.contains_key(group)
+    /// Metadata recorded for `section`, if it has any.
+    pub fn section_meta(&self, section: &str) -> Option<&SectionMeta> {
+        return self.meta.get(section);
+    }
+
+    /// Current content of `section`.
+    pub fn section_content(&self, section: &str) -> Option<&str> {
+        return self.section(section).map(|x| x.as_str());
     }
 
     pub fn add_group(
@@ -159,10 +607,91 @@ impl GitIgnore {
         force: bool,
         igs: &IgnoreGroups,
     ) -> Result<(), Error> {
-        if self.0.contains_key(&group.name) && !force {
+        return self.insert_group(group, force, igs, None);
+    }
+
+    fn insert_group(
+        &mut self,
+        group: &IgnoreGroup,
+        force: bool,
+        igs: &IgnoreGroups,
+        dependency_of: Option<&str>,
+    ) -> Result<(), Error> {
+        if self.section(&group.name).is_some() && !force {
             return Ok(());
         }
 
+        // A pinned group is generated as pinned, anything else is pinned as generated now.
+        let pinned = self.lock()?.get(&group.name).cloned();
+        let group = match &pinned {
+            Some(pinned) => pinned,
+            None => {
+                self.lock()?.pin(group);
+                group
+            }
+        };
+
+        for item in &group.items {
+            if let IgnoreItem::Dependency(dep) = item {
+                self.insert_group(igs.get(dep)?, force, igs, Some(&group.name))?;
+            }
+        }
+
+        let content = GitIgnore::group_content(group);
+        let mut meta = SectionMeta::new(group, &content);
+        meta.dependency_of = dependency_of.map(|x| x.to_string());
+
+        if force {
+            meta.options.push("force".to_string());
+        }
+
+        self.set_section(&group.name, content);
+        self.meta.insert(group.name.clone(), meta);
+
+        return Ok(());
+    }
+
+    /// Regenerates `section` from the catalog, keeping how it was originally added, and
+    /// pins the group to the catalog's current content.
+    pub fn update_group(&mut self, group: &IgnoreGroup) -> Result<(), Error> {
+        self.lock()?.pin(group);
+
+        let content = GitIgnore::group_content(group);
+        let mut meta = SectionMeta::new(group, &content);
+
+        if let Some(old) = self.meta.get(&group.name) {
+            meta.dependency_of = old.dependency_of.clone();
+            meta.options = old.options.clone();
+        }
+
+        self.set_section(&group.name, content);
+        self.meta.insert(group.name.clone(), meta);
+
+        return Ok(());
+    }
+
+    /// Pins `group` as it is now unless the lock already has it.
+    pub fn pin(&mut self, group: &IgnoreGroup) -> Result<(), Error> {
+        let lock = self.lock()?;
+
+        if lock.get(&group.name).is_none() {
+            lock.pin(group);
+        }
+
+        return Ok(());
+    }
+
+    /// The content `group` is generated with here, which is its pinned content if the
+    /// lock has it.
+    pub fn pinned_content(&mut self, group: &IgnoreGroup) -> Result<String, Error> {
+        return Ok(match self.lock()?.get(&group.name) {
+            Some(pinned) => GitIgnore::group_content(pinned),
+            None => GitIgnore::group_content(group),
+        });
+    }
+
+    /// The section content d-ig generates for `group`, leaving out its dependencies.
+    pub fn group_content(group: &IgnoreGroup) -> String {
         let mut string = String::new();
 
         let mut last_was_comment = false;
@@ -170,7 +699,8 @@ impl GitIgnore {
         for item in &group.items {
             match item {
                 IgnoreItem::Item(item) => {
-                    string.push_str(&format!("{item}\n"));
+                    string.push_str(item);
+                    string.push('\n');
                     last_was_comment = false;
                 }
                 IgnoreItem::Comment(comment) => {
@@ -179,49 +709,230 @@ impl GitIgnore {
                         last_was_comment = true;
                     }
 
-                    string.push_str(&format!("# {comment}\n"));
+                    string.push_str("# ");
+                    string.push_str(comment);
+                    string.push('\n');
                 }
-                IgnoreItem::Dependency(dep) => self.add_group(igs.get(&dep)?, force, igs)?,
+                IgnoreItem::Dependency(_) => {}
             }
         }
 
-        self.0.insert(group.name.clone(), string);
-
-        return Ok(());
+        return string;
     }
 
     pub fn add_item(&mut self, item: &str) {
-        for g in self.0.values() {
-            for line in g.lines() {
-                if line.trim_end() == item {
-                    return;
-                }
+        // Most sections don't contain the item at all, which a substring search tells
+        // much faster than comparing line by line.
+        for (_, g) in self.sections.iter().filter(|(_, x)| x.contains(item)) {
+            if g.lines().any(|x| x.trim_end() == item) {
+                return;
             }
         }
 
-        let default = match self.0.get_mut("_") {
-            Some(default) => default,
+        let default = self.section_entry("_");
+        default.push_str(item.trim_end());
+        default.push('\n');
+    }
+
+    /// Removes the lines that are exactly `item` from every section d-ig manages. Content
+    /// outside of d-ig sections is never edited.
+    pub fn remove_item(&mut self, item: &str) {
+        let item = item.trim_end();
+
+        for (name, g) in &mut self.sections {
+            if name == UNMANAGED || !g.lines().any(|x| x.trim_end() == item) {
+                continue;
+            }
+
+            let mut kept = String::with_capacity(g.len());
+
+            for line in g.lines().filter(|x| x.trim_end() != item) {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+
+            *g = kept;
+        }
+    }
+
+    pub fn remove_group(&mut self, group: &str) -> Result<(), Error> {
+        let idx = match self.sections.iter().position(|(x, _)| x == group) {
+            Some(idx) => idx,
             None => {
-                self.0.insert("_".to_string(), "".to_string());
-                self.0.get_mut("_").unwrap()
+                return Err(Error::NotFound(format!(
+                    "{} does not have ignore group '{group}'.",
+                    self.target.file_name()
+                )))
             }
         };
 
-        default.push_str(&format!("\n{}", item.trim_end()));
+        self.sections.remove(idx);
+        self.meta.remove(group);
+        self.lock()?.unpin(group);
+
+        return Ok(());
     }
 
-    pub fn remove_item(&mut self, item: &str) {
-        for g in self.0.values_mut() {
-            *g = g.replace(item, "");
+    /// Unpins the groups that no longer have a section and writes the lock, for when the
+    /// file was replaced as a whole, such as by `--clear` or `--undo`.
+    pub fn prune_lock(&mut self) -> Result<(), Error> {
+        let sections = self.sections();
+        let lock = self.lock()?;
+        lock.retain(|x| sections.iter().any(|section| section == x));
+
+        return lock.write();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::{section_state, SectionState};
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_dir_all, write},
+    };
+
+    /// A repository in the temp dir holding a .gitignore with `content`.
+    fn repo(name: &str, content: &str) -> PathBuf {
+        let dir = temp_dir().join(format!("d-ig-{name}-{}", std::process::id()));
+        create_dir_all(dir.join(".git")).unwrap();
+
+        let path = dir.join(".gitignore");
+        write(&path, content).unwrap();
+
+        return path;
+    }
+
+    #[test]
+    fn merged_content_survives_later_writes() {
+        let content = "# my project\n### Notes ###\nfoo.txt\n!keep.log\n# --- my stuff ---\n#==[ Hand ]==#\n*.tmp\n# END d-ig Notes\n#@d-ig v=1 source=elsewhere\n";
+        let path = repo("merge", content);
+        let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+
+        let mut gitignore = GitIgnore::merge(&path).unwrap();
+        gitignore
+            .add_group(igs.get("Python").unwrap(), false, &igs)
+            .unwrap();
+        gitignore.write().unwrap();
+
+        for group in ["Rust", "Node"] {
+            let mut gitignore = GitIgnore::open(&path).unwrap();
+            assert_eq!(gitignore.section_content(UNMANAGED), Some(content));
+
+            gitignore
+                .add_group(igs.get(group).unwrap(), false, &igs)
+                .unwrap();
+            gitignore.write().unwrap();
         }
+
+        let gitignore = GitIgnore::open(&path).unwrap();
+        assert_eq!(gitignore.section_content(UNMANAGED), Some(content));
+        assert_eq!(gitignore.sections(), ["Python", "Rust", "Node"]);
+        assert!(read_to_string(&path).unwrap().starts_with(content));
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
     }
 
-    pub fn remove_group(&mut self, group: &str) -> Result<(), Error> {
-        match self.0.remove(group) {
-            Some(_) => Ok(()),
-            None => Err(Error(format!(
-                "{GIT_IGNORE} does not have ignore group '{group}'."
-            ))),
+    #[test]
+    fn remove_item_removes_whole_lines_outside_unmanaged_content() {
+        let mut gitignore = GitIgnore::new(Path::new(".gitignore"));
+        gitignore.set_section(UNMANAGED, "*.log\nfoo*.log\n".to_string());
+        gitignore.set_section("Custom", "*.log\nbar*.log\n".to_string());
+        gitignore.set_section("_", "foo*.log\n*.log\n/scratch/\n".to_string());
+
+        gitignore.remove_item("*.log");
+
+        assert_eq!(
+            gitignore.section_content(UNMANAGED),
+            Some("*.log\nfoo*.log\n")
+        );
+        assert_eq!(gitignore.section_content("Custom"), Some("bar*.log\n"));
+        assert_eq!(
+            gitignore.section_content("_"),
+            Some("foo*.log\n/scratch/\n")
+        );
+    }
+
+    fn meta() -> SectionMeta {
+        return SectionMeta {
+            version: META_VERSION,
+            source: "github/gitignore/Global/JetBrains.gitignore".to_string(),
+            catalog: "0.1.1".to_string(),
+            hash: "4f3c2a1b9e8d7c6f".to_string(),
+            dependency_of: None,
+            options: Vec::new(),
+        };
+    }
+
+    #[test]
+    fn section_meta_round_trips() {
+        let plain = meta();
+        assert_eq!(
+            plain.render(),
+            "#@d-ig v=1 source=github/gitignore/Global/JetBrains.gitignore catalog=0.1.1 hash=4f3c2a1b9e8d7c6f"
+        );
+
+        let mut full = meta();
+        full.dependency_of = Some("Rust".to_string());
+        full.options = vec!["force".to_string(), "other".to_string()];
+        assert!(full
+            .render()
+            .ends_with(" dependency-of=Rust options=force,other"));
+
+        for meta in [plain, full] {
+            assert_eq!(SectionMeta::parse(&meta.render()), Some(meta));
         }
     }
+
+    #[test]
+    fn section_meta_skips_what_it_does_not_know() {
+        // A newer format still loads, without the fields this version does not know.
+        let newer = SectionMeta::parse("#@d-ig v=2 hash=00ff future=yes catalog=9.0.0").unwrap();
+        assert_eq!(newer.version, 2);
+        assert_eq!(newer.hash, "00ff");
+        assert_eq!(newer.catalog, "9.0.0");
+        assert!(newer.dependency_of.is_none() && newer.options.is_empty());
+
+        assert!(SectionMeta::parse("#@d-ig v=x hash=00ff").is_none());
+        assert!(SectionMeta::parse("#@d-ig hash=00ff").is_none());
+        assert!(SectionMeta::parse("#@d-ig v=1 source=x").is_none());
+        assert!(SectionMeta::parse("# v=1 hash=00ff").is_none());
+    }
+
+    #[test]
+    fn section_meta_hash_tells_edits_apart() {
+        let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+        let group = igs.get("Rust").unwrap();
+        let content = GitIgnore::group_content(group);
+        let meta = SectionMeta::new(group, &content);
+        let header = HeaderStyle::Banner.header("Rust");
+
+        let path = repo("meta", &format!("{header}\n{}\n{content}", meta.render()));
+        let gitignore = GitIgnore::open(&path).unwrap();
+
+        assert_eq!(gitignore.section_meta("Rust"), Some(&meta));
+        assert_eq!(
+            section_state(&gitignore, &igs, "Rust"),
+            SectionState::UpToDate
+        );
+
+        write(
+            &path,
+            format!("{header}\n{}\n{content}extra/\n", meta.render()),
+        )
+        .unwrap();
+        let gitignore = GitIgnore::open(&path).unwrap();
+
+        assert_ne!(
+            content_hash(gitignore.section_content("Rust").unwrap()),
+            meta.hash
+        );
+        assert_eq!(
+            section_state(&gitignore, &igs, "Rust"),
+            SectionState::Modified
+        );
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
 }
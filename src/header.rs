@@ -0,0 +1,237 @@
+use crate::{
+    r#const::{HEADER_STYLE, META_PREFIX, TITLE_WRAPPER_CLOSE, TITLE_WRAPPER_OPEN},
+    Error,
+};
+
+const MARKER_BEGIN: &str = "# BEGIN d-ig";
+const MARKER_END: &str = "# END d-ig";
+
+/// How section headers are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeaderStyle {
+    /// `#====[ name ]====#`, 90 characters wide.
+    Banner,
+    /// `# --- name ---`
+    Compact,
+    /// `# BEGIN d-ig name` and `# END d-ig name` around the section.
+    Marker,
+}
+
+impl HeaderStyle {
+    pub fn parse(value: &str) -> Result<HeaderStyle, Error> {
+        match value {
+            "banner" => Ok(HeaderStyle::Banner),
+            "compact" => Ok(HeaderStyle::Compact),
+            "marker" => Ok(HeaderStyle::Marker),
+            _ => Err(Error::Usage(format!(
+                "Invalid {HEADER_STYLE} choice \"{value}\". Expected banner, compact or marker."
+            ))),
+        }
+    }
+
+    pub fn header(self, name: &str) -> String {
+        match self {
+            HeaderStyle::Banner => format!("{TITLE_WRAPPER_OPEN} {name} {TITLE_WRAPPER_CLOSE}"),
+            HeaderStyle::Compact => format!("# --- {name} ---"),
+            HeaderStyle::Marker => format!("{MARKER_BEGIN} {name}"),
+        }
+    }
+
+    /// The line closing a section, for styles that have one.
+    pub fn footer(self, name: &str) -> Option<String> {
+        match self {
+            HeaderStyle::Marker => Some(format!("{MARKER_END} {name}")),
+            HeaderStyle::Banner | HeaderStyle::Compact => None,
+        }
+    }
+}
+
+/// A recognized section header: the section name and, for headers d-ig writes, the style.
+/// Headers written by other tools, such as gitignore.io's `### Rust ###`, have no style.
+pub fn read(line: &str) -> Option<(&str, Option<HeaderStyle>)> {
+    let line = line.trim_end();
+
+    // Banners with any number of `=`, so hand-edited ones still count.
+    if let Some(rest) = line.strip_prefix("#=") {
+        let rest = rest.trim_start_matches('=').strip_prefix('[')?;
+        let rest = rest
+            .strip_suffix("=#")?
+            .trim_end_matches('=')
+            .strip_suffix(']')?;
+
+        return named(rest, Some(HeaderStyle::Banner));
+    }
+
+    if let Some(rest) = line.strip_prefix(MARKER_BEGIN) {
+        return named(rest, Some(HeaderStyle::Marker));
+    }
+
+    if let Some(rest) = line.strip_prefix("# ---") {
+        let rest = rest.trim_start_matches('-').strip_suffix("---")?;
+
+        return named(rest.trim_end_matches('-'), Some(HeaderStyle::Compact));
+    }
+
+    // gitignore.io and similar tools.
+    if let Some(rest) = line.strip_prefix("###") {
+        let rest = rest.trim_start_matches('#').strip_suffix("###")?;
+
+        return named(rest.trim_end_matches('#'), None);
+    }
+
+    return None;
+}
+
+/// Like [`read`] for a line followed by `next`. d-ig follows every compact header but
+/// `_`'s with a metadata line, so a compact-looking line without one, such as a
+/// hand-written `# --- my stuff ---`, is an ordinary comment.
+pub fn read_section<'a>(
+    line: &'a str,
+    next: Option<&str>,
+) -> Option<(&'a str, Option<HeaderStyle>)> {
+    let (name, style) = read(line)?;
+    let has_meta = next.is_some_and(|x| x.starts_with(META_PREFIX));
+
+    if style == Some(HeaderStyle::Compact) && name != "_" && !has_meta {
+        return None;
+    }
+
+    return Some((name, style));
+}
+
+/// Like [`read_section`] for a line before the first section, where the content of a
+/// merged file is kept as it was. Only headers d-ig wrote start a section there: ones
+/// followed by metadata, `_`'s, which has none, and banners exactly as older versions
+/// wrote them.
+pub fn read_first_section<'a>(
+    line: &'a str,
+    next: Option<&str>,
+) -> Option<(&'a str, Option<HeaderStyle>)> {
+    let (name, style) = read_section(line, next)?;
+    let has_meta = next.is_some_and(|x| x.starts_with(META_PREFIX));
+    let written = has_meta
+        || (name == "_" && style.is_some())
+        || line.trim_end() == HeaderStyle::Banner.header(name);
+
+    return match written {
+        true => Some((name, style)),
+        false => None,
+    };
+}
+
+/// Whether `line` closes a section written in the marker style.
+pub fn is_end(line: &str) -> bool {
+    return line.trim_end().starts_with(MARKER_END);
+}
+
+fn named(name: &str, style: Option<HeaderStyle>) -> Option<(&str, Option<HeaderStyle>)> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    return Some((name, style));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const META: &str = "#@d-ig v=1 source=x catalog=0.1.1 hash=00ff";
+
+    #[test]
+    fn reads_every_style() {
+        for style in [
+            HeaderStyle::Banner,
+            HeaderStyle::Compact,
+            HeaderStyle::Marker,
+        ] {
+            assert_eq!(
+                read(&style.header("Global/macOS")),
+                Some(("Global/macOS", Some(style)))
+            );
+        }
+
+        assert_eq!(read(&HeaderStyle::Marker.footer("Rust").unwrap()), None);
+        assert!(is_end(&HeaderStyle::Marker.footer("Rust").unwrap()));
+        assert_eq!(HeaderStyle::Banner.footer("Rust"), None);
+    }
+
+    #[test]
+    fn reads_hand_edited_and_foreign_headers() {
+        assert_eq!(
+            read("#==[ Rust ]==#  "),
+            Some(("Rust", Some(HeaderStyle::Banner)))
+        );
+        assert_eq!(
+            read("# ------ Rust ------"),
+            Some(("Rust", Some(HeaderStyle::Compact)))
+        );
+        assert_eq!(read("### macOS Patch ###"), Some(("macOS Patch", None)));
+        assert_eq!(read("##### Rust #####"), Some(("Rust", None)));
+
+        for line in [
+            "# Rust",
+            "#==[  ]==#",
+            "### ###",
+            "# --- Rust",
+            "## Rust ##",
+        ] {
+            assert_eq!(read(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn compact_headers_need_metadata_except_for_items() {
+        let header = HeaderStyle::Compact.header("Rust");
+
+        assert_eq!(
+            read_section(&header, Some(META)),
+            Some(("Rust", Some(HeaderStyle::Compact)))
+        );
+        assert_eq!(read_section(&header, Some("target/")), None);
+        assert_eq!(read_section(&header, None), None);
+        assert_eq!(
+            read_section(&HeaderStyle::Compact.header("_"), Some("foo")),
+            Some(("_", Some(HeaderStyle::Compact)))
+        );
+
+        // Other styles don't look like comments people write, so they need none.
+        assert!(read_section(&HeaderStyle::Banner.header("Rust"), None).is_some());
+        assert!(read_section(&HeaderStyle::Marker.header("Rust"), None).is_some());
+        assert!(read_section("### Rust ###", None).is_some());
+    }
+
+    #[test]
+    fn first_section_only_starts_at_headers_d_ig_wrote() {
+        // Foreign and hand-written headers in content above the sections stay content.
+        assert_eq!(read_first_section("### Notes ###", Some("foo.txt")), None);
+        assert_eq!(read_first_section("#==[ Notes ]==#", Some("foo.txt")), None);
+        assert_eq!(read_first_section("# --- my stuff ---", None), None);
+        assert_eq!(
+            read_first_section(&HeaderStyle::Marker.header("Rust"), None),
+            None
+        );
+
+        assert_eq!(
+            read_first_section("### Rust ###", Some(META)),
+            Some(("Rust", None))
+        );
+
+        for style in [
+            HeaderStyle::Banner,
+            HeaderStyle::Compact,
+            HeaderStyle::Marker,
+        ] {
+            assert!(read_first_section(&style.header("Rust"), Some(META)).is_some());
+            assert!(read_first_section(&style.header("_"), Some("foo")).is_some());
+        }
+
+        // Banners exactly as older versions wrote them, before there was metadata.
+        assert_eq!(
+            read_first_section(&HeaderStyle::Banner.header("Rust"), Some("target/")),
+            Some(("Rust", Some(HeaderStyle::Banner)))
+        );
+    }
+}
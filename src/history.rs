@@ -0,0 +1,302 @@
+use crate::{
+    git_index::Repo,
+    r#const::{HISTORY_DIR, HISTORY_LIMIT},
+    target::Target,
+    Error,
+};
+use std::{
+    fs::{create_dir_all, read_dir, read_to_string, remove_file, rename, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Previous versions of a single ignore file, kept under the repository's git directory.
+pub struct History {
+    dir: PathBuf,
+}
+
+pub struct Entry {
+    pub path: PathBuf,
+    /// Milliseconds since the unix epoch when the version was replaced.
+    pub timestamp: u128,
+}
+
+impl History {
+    pub fn for_file(path: &Path) -> Option<History> {
+        let dir = path.parent()?;
+        let repo = Repo::find(dir)?;
+
+        let relative = path.strip_prefix(&repo.root).ok()?;
+        let key: Vec<String> = relative
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        return Some(History {
+            dir: repo.git_dir.join(HISTORY_DIR).join(key.join("%")),
+        });
+    }
+
+    /// Entries from newest to oldest.
+    pub fn entries(&self) -> Result<Vec<Entry>, Error> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let dir = match read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(err) => return Err(Error::Io("Could not read history".to_string(), err)),
+        };
+
+        let mut entries = Vec::new();
+
+        for file in dir.flatten() {
+            let name = file.file_name().to_string_lossy().to_string();
+
+            if let Some(timestamp) = name.split('-').next().and_then(|x| x.parse().ok()) {
+                entries.push(Entry {
+                    path: file.path(),
+                    timestamp,
+                });
+            }
+        }
+
+        entries.sort_by(|a, b| b.path.cmp(&a.path));
+
+        return Ok(entries);
+    }
+
+    fn record(&self, content: &str) -> Result<(), Error> {
+        if let Err(err) = create_dir_all(&self.dir) {
+            return Err(Error::Io(
+                "Could not create history directory".to_string(),
+                err,
+            ));
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_millis())
+            .unwrap_or(0);
+
+        let mut counter = 0;
+        let mut path = self.dir.join(format!("{timestamp:020}-{counter:03}"));
+
+        while path.exists() {
+            counter += 1;
+            path = self.dir.join(format!("{timestamp:020}-{counter:03}"));
+        }
+
+        write_atomic(&path, content)?;
+
+        for entry in self.entries()?.iter().skip(HISTORY_LIMIT) {
+            let _ = remove_file(&entry.path);
+        }
+
+        return Ok(());
+    }
+
+    /// Restores the version from `steps` writes ago, dropping it and every newer entry.
+    pub fn undo(&self, target: &Path, steps: usize) -> Result<(), Error> {
+        let entries = self.entries()?;
+
+        if steps == 0 || steps > entries.len() {
+            return Err(Error::NotFound(format!(
+                "{} only has {} previous version(s).",
+                Target::of(target).file_name(),
+                entries.len()
+            )));
+        }
+
+        let entry = &entries[steps - 1];
+        let content = match read_to_string(&entry.path) {
+            Ok(content) => content,
+            Err(err) => return Err(Error::Io("Could not read history".to_string(), err)),
+        };
+
+        write_atomic(target, &content)?;
+
+        for entry in &entries[..steps] {
+            let _ = remove_file(&entry.path);
+        }
+
+        return Ok(());
+    }
+}
+
+/// Replaces `path` with `content`, keeping the previous version in its history.
+pub fn save(path: &Path, content: &str) -> Result<(), Error> {
+    return save_with(path, |out| out.write_all(content.as_bytes()));
+}
+
+/// Replaces `path` with what `write` streams out, keeping the previous version in its
+/// history. The new content is compared with the previous one as it is written instead
+/// of being built up in memory first.
+pub fn save_with(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<(), Error> {
+    let history = History::for_file(path);
+    let previous = history.as_ref().and_then(|_| read_to_string(path).ok());
+    let mut changed = true;
+
+    let temp = write_temp(path, |file| {
+        let mut out = Compare {
+            inner: BufWriter::new(file),
+            previous: previous.as_deref().unwrap_or("").as_bytes(),
+            written: 0,
+            same: true,
+        };
+
+        write(&mut out)?;
+        out.flush()?;
+
+        changed = !out.same || out.written != out.previous.len();
+        return Ok(());
+    })?;
+
+    if let (Some(history), Some(previous), true) = (history, previous, changed) {
+        if let Err(err) = history.record(&previous) {
+            let _ = remove_file(&temp);
+            return Err(err);
+        }
+    }
+
+    return replace(&temp, path);
+}
+
+/// Passes writes through to `inner` and tracks whether they still match `previous`.
+struct Compare<'a, W: Write> {
+    inner: W,
+    previous: &'a [u8],
+    written: usize,
+    same: bool,
+}
+
+impl<W: Write> Write for Compare<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+
+        self.same =
+            self.same && self.previous.get(self.written..self.written + len) == Some(&buf[..len]);
+        self.written += len;
+
+        return Ok(len);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+/// Writes to a temporary file next to `path` and renames it into place, so a failed
+/// write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), Error> {
+    let temp = write_temp(path, |file| file.write_all(content.as_bytes()))?;
+
+    return replace(&temp, path);
+}
+
+/// Creates the temporary file `path` is replaced with and fills it with `write`.
+fn write_temp(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> Result<PathBuf, Error> {
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let temp = path.with_file_name(format!(".{name}.d-ig.tmp"));
+
+    let result = File::create(&temp).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()
+    });
+
+    if let Err(err) = result {
+        let _ = remove_file(&temp);
+        return Err(Error::Io(
+            format!("Could not write to {}", path.to_string_lossy()),
+            err,
+        ));
+    }
+
+    return Ok(temp);
+}
+
+fn replace(temp: &Path, path: &Path) -> Result<(), Error> {
+    if let Err(err) = rename(temp, path) {
+        let _ = remove_file(temp);
+        return Err(Error::Io(
+            format!("Could not write to {}", path.to_string_lossy()),
+            err,
+        ));
+    }
+
+    return Ok(());
+}
+
+/// Formats a unix timestamp in milliseconds as `YYYY-MM-DD HH:MM:SS` UTC.
+pub fn format_timestamp(millis: u128) -> String {
+    let secs = (millis / 1000) as i64;
+    let days = secs.div_euclid(86400);
+    let time = secs.rem_euclid(86400);
+
+    // Days to civil date, from Howard Hinnant's date algorithms.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    return format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{header::HeaderStyle, GitIgnore};
+    use std::{env::temp_dir, fs::remove_dir_all};
+
+    #[test]
+    fn no_op_write_adds_no_entry() {
+        let dir = temp_dir().join(format!("d-ig-history-{}", std::process::id()));
+        create_dir_all(dir.join(".git")).unwrap();
+
+        let path = dir.join(".gitignore");
+        let mut content = "local.txt\n".to_string();
+
+        for name in ["Rust", "Python", "Node", "Go", "Java"] {
+            content += &format!("{}\n/{name}/\n\n", HeaderStyle::Banner.header(name));
+        }
+
+        std::fs::write(&path, content).unwrap();
+        let history = History::for_file(&path).unwrap();
+
+        let mut gitignore = GitIgnore::open(&path).unwrap();
+        gitignore.add_item("foo");
+        gitignore.write().unwrap();
+        assert_eq!(history.entries().unwrap().len(), 1);
+
+        for _ in 0..4 {
+            let mut gitignore = GitIgnore::open(&path).unwrap();
+            gitignore.add_item("foo");
+            gitignore.write().unwrap();
+        }
+
+        assert_eq!(history.entries().unwrap().len(), 1);
+
+        remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,82 +1,132 @@
 use crate::{
-    r#const::{BLUE, GREEN, IGNORE_FILE, RESET, YELLOW},
+    output::{self, paint},
+    r#const::{ALIASES, BLUE, CATEGORY, GREEN, YELLOW},
+    target::Target,
     Error,
 };
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, ops::Range, sync::OnceLock};
 
 #[derive(Debug)]
 pub struct IgnoreGroups {
-    groups: HashMap<String, IgnoreGroup>,
+    target: Target,
+    catalog: Cow<'static, str>,
+    /// Groups by name, parsed from their lines of the catalog the first time they are used.
+    groups: HashMap<String, CatalogEntry>,
+    /// Other names for groups, matched without regard to case.
+    aliases: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+struct CatalogEntry {
+    /// Byte range of the group's lines in the catalog, without its `# Name` line.
+    lines: Range<usize>,
+    group: OnceLock<IgnoreGroup>,
 }
 
 impl IgnoreGroups {
-    pub fn parse() -> Result<IgnoreGroups, Error> {
+    /// Indexes the embedded catalog of `target`.
+    pub fn parse(target: Target) -> Result<IgnoreGroups, Error> {
+        return IgnoreGroups::index(Cow::Borrowed(target.catalog()), target);
+    }
+
+    /// Reads a catalog of `# Name` lines followed by `Item(...)`, `Comment(...)` and
+    /// `Dependency(...)` lines, with groups for files of `target`.
+    pub fn parse_catalog(catalog: &str, target: Target) -> Result<IgnoreGroups, Error> {
+        return IgnoreGroups::index(Cow::Owned(catalog.to_string()), target);
+    }
+
+    /// Finds where each group's lines are. Only the `# Name` lines are looked at here, the
+    /// rest of a group is parsed by [`IgnoreGroups::get`] when it is first needed.
+    fn index(catalog: Cow<'static, str>, target: Target) -> Result<IgnoreGroups, Error> {
         let mut groups = HashMap::new();
-        let mut active_group = None;
-        for line in IGNORE_FILE.lines() {
-            if line.is_empty() {
-                continue;
-            }
+        let mut active: Option<(&str, usize)> = None;
+        let mut offset = 0;
 
-            if line.starts_with("#") {
-                let group_name = line.strip_prefix("#").unwrap().trim();
-                groups.insert(
-                    group_name.to_string(),
-                    IgnoreGroup {
-                        items: Vec::new(),
-                        name: group_name.to_string(),
-                    },
-                );
-                active_group = Some(groups.get_mut(group_name).unwrap());
-                continue;
-            }
+        for line in catalog.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
 
-            if line.starts_with("Item") {
-                let file_name = line
-                    .strip_prefix("Item(")
-                    .unwrap()
-                    .strip_suffix(")")
-                    .unwrap()
-                    .trim();
+            let name = match line.strip_prefix('#') {
+                Some(name) => name.trim(),
+                None => {
+                    if active.is_none() && !line.trim_end_matches(['\r', '\n']).is_empty() {
+                        return Err(IgnoreGroups::invalid(line));
+                    }
 
-                if let Some(ref mut ig) = active_group {
-                    ig.items.push(IgnoreItem::Item(file_name.to_string()));
                     continue;
                 }
+            };
+
+            if let Some((active, body)) = active {
+                groups.insert(active.to_string(), CatalogEntry::new(body..start));
             }
 
-            if line.starts_with("Comment") {
-                let file_name = line
-                    .strip_prefix("Comment(")
-                    .unwrap()
-                    .strip_suffix(")")
-                    .unwrap()
-                    .trim();
+            active = Some((name, offset));
+        }
 
-                if let Some(ref mut ig) = active_group {
-                    ig.items.push(IgnoreItem::Comment(file_name.to_string()));
-                    continue;
-                }
-            }
+        if let Some((active, body)) = active {
+            groups.insert(active.to_string(), CatalogEntry::new(body..offset));
+        }
 
-            if line.starts_with("Dependency") {
-                let file_name = line
-                    .strip_prefix("Dependency(")
-                    .unwrap()
-                    .strip_suffix(")")
-                    .unwrap()
-                    .trim();
+        let aliases = ALIASES
+            .iter()
+            .map(|(alias, group)| (alias.to_string(), group.to_string()))
+            .collect();
 
-                if let Some(ref mut ig) = active_group {
-                    ig.items.push(IgnoreItem::Dependency(file_name.to_string()));
-                    continue;
-                }
+        return Ok(IgnoreGroups {
+            target,
+            catalog,
+            groups,
+            aliases,
+        });
+    }
+
+    /// Parses the catalog lines of the group called `name`.
+    fn parse_group(&self, name: &str, lines: &str) -> Result<IgnoreGroup, Error> {
+        let mut items = Vec::new();
+
+        for line in lines.lines() {
+            if line.is_empty() {
+                continue;
             }
 
-            return Err(Error(format!("Could not parse ingore group '{}'", line)));
+            let item = |prefix: &str| {
+                return line
+                    .strip_prefix(prefix)
+                    .and_then(|x| x.strip_prefix('('))
+                    .and_then(|x| x.strip_suffix(')'))
+                    .map(|x| x.trim().to_string());
+            };
+
+            if let Some(item) = item("Item") {
+                items.push(IgnoreItem::Item(item));
+            } else if let Some(comment) = item("Comment") {
+                items.push(IgnoreItem::Comment(comment));
+            } else if let Some(dep) = item("Dependency") {
+                items.push(IgnoreItem::Dependency(dep));
+            } else {
+                return Err(IgnoreGroups::invalid(line));
+            }
         }
 
-        return Ok(IgnoreGroups { groups });
+        return Ok(IgnoreGroup {
+            items,
+            name: name.to_string(),
+            category: Category::of(name),
+            source: self.target.template_source(name),
+        });
+    }
+
+    fn invalid(line: &str) -> Error {
+        return Error::Catalog(format!(
+            "Could not parse ingore group '{}'",
+            line.trim_end()
+        ));
+    }
+
+    /// The kind of ignore file the catalog's groups are for.
+    pub fn target(&self) -> Target {
+        return self.target;
     }
 
     pub fn filter(&self, filters: &Vec<String>) -> Vec<String> {
@@ -100,10 +150,11 @@ impl IgnoreGroups {
         return keys;
     }
 
-    pub fn cat(&self, key: &String) {
-        let ig = &self.groups[key];
+    pub fn cat(&self, key: &String) -> Result<(), Error> {
+        let ig = self.get(key)?;
 
         let mut string = format!("##### {key} #####\n");
+        string += &paint(BLUE, format!("Source: {}\n", ig.source));
 
         for item in &ig.items {
             string += &item.doc();
@@ -113,34 +164,231 @@ impl IgnoreGroups {
         string += &format!("##### {key} #####");
 
         println!("{string}\n");
+
+        return Ok(());
     }
 
+    /// Prints `keys` in columns under a heading for each category.
     pub fn cat_keys(&self, keys: Vec<String>) {
-        for (idx, key) in keys.into_iter().enumerate() {
-            let color = [GREEN, YELLOW, BLUE][idx % 3];
-            if idx % 4 == 3 {
-                println!("{color}{key}{RESET}");
-            } else {
-                print!("{color}{key}{RESET}, ");
+        for category in [Category::Root, Category::Global, Category::Community] {
+            let keys: Vec<String> = keys
+                .iter()
+                .filter(|x| Category::of(x) == category)
+                .cloned()
+                .collect();
+
+            if keys.is_empty() {
+                continue;
+            }
+
+            println!(
+                "{}",
+                paint(BLUE, format!("{} ({})", category.title(), keys.len()))
+            );
+            print!("{}", output::columns(&keys));
+        }
+    }
+
+    /// Checks that a group and everything it depends on exists.
+    pub fn validate(&self, group: &str) -> Result<(), Error> {
+        let mut pending = vec![group.to_string()];
+        let mut seen = Vec::new();
+
+        while let Some(name) = pending.pop() {
+            let ig = self.get(&name)?;
+            seen.push(name);
+
+            for item in &ig.items {
+                if let IgnoreItem::Dependency(dep) = item {
+                    if !seen.contains(dep) {
+                        pending.push(dep.clone());
+                    }
+                }
             }
         }
+
+        return Ok(());
+    }
+
+    /// `groups` and everything they depend on, sorted.
+    pub fn with_dependencies(&self, groups: &[String]) -> Result<Vec<String>, Error> {
+        let mut pending = groups.to_vec();
+        let mut seen: Vec<String> = Vec::new();
+
+        while let Some(name) = pending.pop() {
+            let group = self.get(&name)?;
+
+            if seen.contains(&group.name) {
+                continue;
+            }
+
+            for item in &group.items {
+                if let IgnoreItem::Dependency(dep) = item {
+                    pending.push(dep.clone());
+                }
+            }
+
+            seen.push(group.name.clone());
+        }
+
+        seen.sort();
+
+        return Ok(seen);
+    }
+
+    /// Finds the groups a loosely typed name could mean: an exact name or alias, or a
+    /// case-insensitive match on the full name or the part after the last `/`.
+    pub fn lookup(&self, name: &str) -> Vec<String> {
+        if let Ok(group) = self.get(name) {
+            return vec![group.name.clone()];
+        }
+
+        let name = name.to_lowercase();
+
+        let mut keys: Vec<String> = self
+            .groups
+            .keys()
+            .filter(|key| {
+                let key = key.to_lowercase();
+                key == name || key.rsplit('/').next() == Some(name.as_str())
+            })
+            .cloned()
+            .collect();
+
+        keys.sort();
+
+        return keys;
+    }
+
+    /// Every group of the catalog, parsed.
+    pub(crate) fn into_groups(self) -> Result<HashMap<String, IgnoreGroup>, Error> {
+        let mut groups = HashMap::new();
+
+        for name in self.groups.keys() {
+            groups.insert(name.clone(), self.get(name)?.clone());
+        }
+
+        return Ok(groups);
+    }
+
+    /// Adds `alias` as another name for `group`, replacing a built-in alias of that name.
+    pub fn add_alias(&mut self, alias: &str, group: &str) {
+        self.aliases.retain(|x, _| !x.eq_ignore_ascii_case(alias));
+        self.aliases.insert(alias.to_string(), group.to_string());
+    }
+
+    /// Aliases whose group is in the catalog, sorted by alias.
+    pub fn aliases(&self) -> Vec<(String, String)> {
+        let mut aliases: Vec<(String, String)> = self
+            .aliases
+            .iter()
+            .filter(|(_, group)| self.groups.contains_key(*group))
+            .map(|(alias, group)| (alias.clone(), group.clone()))
+            .collect();
+
+        aliases.sort();
+
+        return aliases;
     }
 
+    /// The name of the group `name` refers to, which differs from it for aliases.
+    pub fn canonical(&self, name: &str) -> String {
+        return match self.get(name) {
+            Ok(group) => group.name.clone(),
+            Err(_) => name.to_string(),
+        };
+    }
+
+    /// The group called `group`, or the group it is an alias of.
     pub fn get(&self, group: &str) -> Result<&IgnoreGroup, Error> {
-        match self.groups.get(group) {
-            Some(group) => Ok(group),
-            None => Err(Error(format!("Ignore group {group} does not exist."))),
+        let name = match self.groups.contains_key(group) {
+            true => Some(group),
+            false => self
+                .aliases
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(group))
+                .map(|(_, x)| x.as_str()),
+        };
+
+        let (name, entry) = match name.and_then(|x| self.groups.get_key_value(x)) {
+            Some(found) => found,
+            None => {
+                return Err(Error::NotFound(format!(
+                    "Ignore group {group} does not exist."
+                )))
+            }
+        };
+
+        if let Some(group) = entry.group.get() {
+            return Ok(group);
         }
+
+        let group = self.parse_group(name, &self.catalog[entry.lines.clone()])?;
+
+        return Ok(entry.group.get_or_init(|| group));
     }
 }
 
-#[derive(Debug)]
+impl CatalogEntry {
+    fn new(lines: Range<usize>) -> CatalogEntry {
+        return CatalogEntry {
+            lines,
+            group: OnceLock::new(),
+        };
+    }
+}
+
+/// Where in the catalog a group comes from, following the folders of github/gitignore.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    /// Languages, frameworks and tools at the root of the catalog.
+    Root,
+    /// Editors and operating systems, meant for a global gitignore.
+    Global,
+    /// Templates contributed and maintained by the community.
+    Community,
+}
+
+impl Category {
+    pub fn parse(value: &str) -> Result<Category, Error> {
+        match value.to_lowercase().as_str() {
+            "root" => Ok(Category::Root),
+            "global" => Ok(Category::Global),
+            "community" => Ok(Category::Community),
+            _ => Err(Error::Usage(format!(
+                "Invalid {CATEGORY} \"{value}\". Expected root, global or community."
+            ))),
+        }
+    }
+
+    /// The category of the group called `name`.
+    pub fn of(name: &str) -> Category {
+        match name.split('/').next() {
+            Some("Global") => Category::Global,
+            Some("community") => Category::Community,
+            _ => Category::Root,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Category::Root => "Root",
+            Category::Global => "Global",
+            Category::Community => "Community",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct IgnoreGroup {
     pub name: String,
+    pub category: Category,
+    /// Catalog template the group was taken from.
+    pub source: String,
     pub items: Vec<IgnoreItem>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum IgnoreItem {
     Item(String),
     Comment(String),
@@ -150,9 +398,32 @@ pub enum IgnoreItem {
 impl IgnoreItem {
     fn doc(&self) -> String {
         match self {
-            IgnoreItem::Item(item) => format!("{GREEN}Item({item}){RESET}"),
-            IgnoreItem::Comment(comment) => format!("{YELLOW}Comment({comment}){RESET}"),
-            IgnoreItem::Dependency(dep) => format!("{BLUE}Comment({dep}){RESET}"),
+            IgnoreItem::Item(item) => paint(GREEN, format!("Item({item})")),
+            IgnoreItem::Comment(comment) => paint(YELLOW, format!("Comment({comment})")),
+            IgnoreItem::Dependency(dep) => paint(BLUE, format!("Dependency({dep})")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Target;
+    use std::{sync::OnceLock, thread};
+
+    #[test]
+    fn catalog_can_be_shared_across_threads() {
+        static IGS: OnceLock<IgnoreGroups> = OnceLock::new();
+        let igs = IGS.get_or_init(|| IgnoreGroups::parse(Target::Gitignore).unwrap());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| thread::spawn(|| IGS.get().unwrap().get("Rust").unwrap().name.clone()))
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "Rust");
+        }
+
+        assert!(igs.get("Rust").is_ok());
+    }
+}
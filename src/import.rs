@@ -0,0 +1,152 @@
+use crate::{
+    gitignore::GitIgnore,
+    header::HeaderStyle,
+    ignore_groups::{Category, IgnoreGroups},
+    output::paint,
+    plan::{Change, Plan},
+    r#const::{BLUE, GREEN, YELLOW},
+    Error,
+};
+use std::{fs::read_to_string, path::PathBuf};
+
+/// Lines gitignore.io writes around the templates, which are dropped on import.
+const BOILERPLATE: &[&str] = &["# Created by http", "# Edit at http", "# End of http"];
+
+/// Turns a file generated by gitignore.io into managed sections. Each `### Name ###`
+/// block becomes the matching catalog group, and every line that is not already covered
+/// by one, including blocks without a match and custom lines, is kept in the `_` section.
+pub fn import(
+    igs: &IgnoreGroups,
+    file: Option<&String>,
+    style: Option<HeaderStyle>,
+) -> Result<(), Error> {
+    let target = GitIgnore::path_or_build_location(igs.target())?;
+
+    let source = match file {
+        Some(file) => PathBuf::from(file),
+        None => target.clone(),
+    };
+
+    let content = match read_to_string(&source) {
+        Ok(content) => content,
+        Err(err) => {
+            return Err(Error::Io(
+                format!("Could not read {}", source.display()),
+                err,
+            ))
+        }
+    };
+
+    let mut groups: Vec<String> = Vec::new();
+    let mut kept: Vec<String> = Vec::new();
+    // Inside a block replaced by a catalog group only lines the group lacks are kept.
+    let mut in_group = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        if BOILERPLATE.iter().any(|x| trimmed.starts_with(x)) {
+            in_group = false;
+            continue;
+        }
+
+        if let Some(name) = GitIgnore::section_name(line) {
+            // Single items added by d-ig stay single items.
+            if name == "_" {
+                in_group = false;
+                continue;
+            }
+
+            let patch = name.strip_suffix(" Patch");
+
+            match resolve(igs, patch.unwrap_or(name)) {
+                Some(group) => {
+                    match patch {
+                        Some(_) => {
+                            println!("{name} -> {}, extra lines kept in _", paint(GREEN, &group))
+                        }
+                        None => println!("{name} -> {}", paint(GREEN, &group)),
+                    }
+
+                    if !groups.contains(&group) {
+                        groups.push(group);
+                    }
+
+                    in_group = true;
+                }
+                None => {
+                    println!("{name}: {}", paint(YELLOW, "no matching group, kept in _"));
+                    kept.push(format!("# {name}"));
+                    in_group = false;
+                }
+            }
+
+            continue;
+        }
+
+        if trimmed.is_empty() || (in_group && trimmed.starts_with('#')) {
+            continue;
+        }
+
+        kept.push(trimmed.to_string());
+    }
+
+    let mut gitignore = match source == target {
+        true => GitIgnore::new(&target),
+        false if target.exists() => GitIgnore::open(&target)?,
+        false => GitIgnore::new(&target),
+    };
+
+    if let Some(style) = style {
+        gitignore.set_style(style);
+    }
+
+    let existing = gitignore.sections();
+    let changes = groups
+        .into_iter()
+        .filter(|x| !existing.contains(x))
+        .map(Change::AddG)
+        .collect();
+
+    let plan = Plan::new(changes, &gitignore, igs);
+
+    if !plan.problems.is_empty() {
+        return Err(Error::Many(plan.problems));
+    }
+
+    plan.apply(&mut gitignore, igs)?;
+
+    for line in kept {
+        gitignore.add_item(&line);
+    }
+
+    let custom = gitignore
+        .section_content("_")
+        .map_or(0, |x| x.lines().count());
+
+    println!(
+        "{}",
+        paint(
+            BLUE,
+            format!(
+                "{custom} custom line(s) in _, written to {}.",
+                target.display()
+            )
+        )
+    );
+
+    return gitignore.write();
+}
+
+/// The catalog group a gitignore.io template name stands for, if any. Variants such as `JetBrains+all` map to their base template.
+fn resolve(igs: &IgnoreGroups, name: &str) -> Option<String> {
+    let name = name.split('+').next().unwrap_or(name).trim();
+    let candidates = igs.lookup(name);
+
+    // The same name can exist in several catalog folders, so prefer the better known one.
+    return candidates
+        .iter()
+        .find(|x| igs.get(x).is_ok_and(|x| x.category != Category::Community))
+        .or(candidates.first())
+        .cloned();
+}
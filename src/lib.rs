@@ -0,0 +1,49 @@
+//! Library side of Dechow Git Ignore Builder (d-ig).
+//!
+//! Everything the `d-ig` binary does to a .gitignore is available here for build
+//! scripts and project generators, working on explicit paths instead of the
+//! current directory.
+//!
+//! ```no_run
+//! use d_ig::{target::Target, Change, GitIgnore, IgnoreGroups, Plan};
+//! use std::path::Path;
+//!
+//! let igs = IgnoreGroups::parse(Target::Gitignore)?;
+//! let mut gitignore = GitIgnore::open(Path::new("my-project/.gitignore"))?;
+//!
+//! let plan = Plan::new(vec![Change::AddG("Rust".to_string())], &gitignore, &igs);
+//! plan.apply(&mut gitignore, &igs)?;
+//! gitignore.write()?;
+//! # Ok::<(), d_ig::Error>(())
+//! ```
+
+#![allow(clippy::needless_return)]
+
+pub mod r#const;
+pub mod detect;
+mod error;
+pub mod explain;
+pub mod export;
+pub mod git_index;
+mod gitignore;
+pub mod header;
+pub mod history;
+mod ignore_groups;
+pub mod import;
+pub mod lock;
+pub mod manifest;
+pub mod output;
+pub mod pattern;
+pub mod pick;
+mod plan;
+pub mod preview;
+pub mod search;
+pub mod status;
+pub mod target;
+pub mod wizard;
+pub mod workspace;
+
+pub use error::Error;
+pub use gitignore::{content_hash, GitIgnore, OnExists, Rule, SectionMeta};
+pub use ignore_groups::{Category, IgnoreGroup, IgnoreGroups, IgnoreItem};
+pub use plan::{Change, Plan};
@@ -0,0 +1,327 @@
+use crate::{
+    history::write_atomic,
+    ignore_groups::{IgnoreGroup, IgnoreGroups, IgnoreItem},
+    r#const::{LOCK, MANIFEST, UPDATE},
+    target::Target,
+    Error,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+/// Catalog groups pinned in `.d-ig.lock` as they were when first generated, so every d-ig
+/// version regenerates the same sections until `update` is run. The lock is kept when the
+/// file already exists or a `.d-ig.toml` sits next to the ignore file, and is written in
+/// the catalog's own format with a table per [`Target`]:
+/// ```text
+/// [gitignore]
+/// # Rust
+/// Comment(Generated by Cargo)
+/// Item(/target/)
+/// ```
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    target: Target,
+    enabled: bool,
+    /// Pinned groups of the current target.
+    groups: HashMap<String, IgnoreGroup>,
+    /// Tables of the other targets, written back as they were read.
+    others: BTreeMap<String, String>,
+    changed: bool,
+}
+
+impl Lock {
+    /// The lock next to the ignore file at `path`.
+    pub fn for_file(path: &Path) -> Result<Lock, Error> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let target = Target::of(path);
+        let path = dir.join(LOCK);
+
+        let mut lock = Lock {
+            path: path.clone(),
+            target,
+            enabled: path.is_file() || dir.join(MANIFEST).is_file(),
+            groups: HashMap::new(),
+            others: BTreeMap::new(),
+            changed: false,
+        };
+
+        if !path.is_file() {
+            return Ok(lock);
+        }
+
+        let content = match read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => return Err(Error::Io(format!("Could not read {LOCK}"), err)),
+        };
+
+        let mut tables: BTreeMap<String, String> = BTreeMap::new();
+        let mut table = None;
+
+        // Lines before the first table are comments.
+        for line in content.lines() {
+            if let Some(name) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+                table = Some(name.to_string());
+                continue;
+            }
+
+            if let Some(table) = &table {
+                let body = tables.entry(table.clone()).or_default();
+                *body += line;
+                *body += "\n";
+            }
+        }
+
+        for (name, body) in tables {
+            match name == target.file_name().trim_start_matches('.') {
+                true => match IgnoreGroups::parse_catalog(&body, target)
+                    .and_then(|x| x.into_groups())
+                {
+                    Ok(groups) => lock.groups = groups,
+                    Err(err) => return Err(Error::Parse(format!("Could not parse {LOCK}: {err}"))),
+                },
+                false => {
+                    lock.others.insert(name, body);
+                }
+            }
+        }
+
+        return Ok(lock);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&IgnoreGroup> {
+        return self.groups.get(name);
+    }
+
+    /// Records `group` as it is now, replacing an older pin.
+    pub fn pin(&mut self, group: &IgnoreGroup) {
+        if !self.enabled || self.groups.get(&group.name) == Some(group) {
+            return;
+        }
+
+        self.groups.insert(group.name.clone(), group.clone());
+        self.changed = true;
+    }
+
+    pub fn unpin(&mut self, name: &str) {
+        if self.groups.remove(name).is_some() {
+            self.changed = true;
+        }
+    }
+
+    /// Unpins every group `keep` returns false for.
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        let pinned = self.groups.len();
+        self.groups.retain(|name, _| keep(name));
+
+        if self.groups.len() != pinned {
+            self.changed = true;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut content = format!(
+            "# Written by d-ig, do not edit. Groups are regenerated as pinned here until\n# `d-ig {UPDATE}` moves them to the current catalog.\n"
+        );
+
+        let target = self.target.file_name().trim_start_matches('.');
+        let mut tables = self.others.clone();
+        let mut names: Vec<&String> = self.groups.keys().collect();
+        names.sort();
+
+        let mut body = String::new();
+
+        for name in names {
+            body += &format!("# {name}\n");
+
+            for item in &self.groups[name].items {
+                body += &match item {
+                    IgnoreItem::Item(item) => format!("Item({item})\n"),
+                    IgnoreItem::Comment(comment) => format!("Comment({comment})\n"),
+                    IgnoreItem::Dependency(dep) => format!("Dependency({dep})\n"),
+                };
+            }
+
+            body += "\n";
+        }
+
+        tables.insert(target.to_string(), body);
+
+        for (name, body) in tables {
+            let body = body.trim_matches('\n');
+
+            if !body.is_empty() {
+                content += &format!("\n[{name}]\n{body}\n");
+            }
+        }
+
+        return content;
+    }
+
+    /// Writes the lock if a group was pinned or unpinned since it was read.
+    pub fn write(&self) -> Result<(), Error> {
+        if !self.enabled || !self.changed {
+            return Ok(());
+        }
+
+        return write_atomic(&self.path, &self.render());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        history,
+        status::{drifts, section_state, update_sections, SectionState},
+        GitIgnore,
+    };
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_dir_all, write},
+    };
+
+    /// A repository in the temp dir with an empty .gitignore and an empty lock.
+    fn repo(name: &str) -> PathBuf {
+        let dir = temp_dir().join(format!("d-ig-lock-{name}-{}", std::process::id()));
+        create_dir_all(dir.join(".git")).unwrap();
+        write(dir.join(LOCK), "").unwrap();
+
+        let path = dir.join(".gitignore");
+        write(&path, "").unwrap();
+
+        return path;
+    }
+
+    fn add(path: &Path, igs: &IgnoreGroups, group: &str) {
+        let mut gitignore = GitIgnore::open(path).unwrap();
+        gitignore
+            .add_group(igs.get(group).unwrap(), false, igs)
+            .unwrap();
+        gitignore.write().unwrap();
+    }
+
+    fn pinned(path: &Path, group: &str) -> Option<IgnoreGroup> {
+        return Lock::for_file(path).unwrap().get(group).cloned();
+    }
+
+    #[test]
+    fn pinned_group_is_generated_as_pinned_until_update() {
+        let path = repo("pin");
+        let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+        let rust = igs.get("Rust").unwrap();
+
+        add(&path, &igs, "Rust");
+        assert_eq!(pinned(&path, "Rust").as_ref(), Some(rust));
+
+        // The catalog of a newer d-ig no longer matches the pin.
+        write(
+            path.with_file_name(LOCK),
+            "[gitignore]\n# Rust\nComment(Old Rust)\nItem(/target/)\n",
+        )
+        .unwrap();
+        write(&path, "").unwrap();
+        add(&path, &igs, "Rust");
+
+        let mut gitignore = GitIgnore::open(&path).unwrap();
+        let required = ["Rust".to_string()];
+
+        assert_eq!(
+            gitignore.section_content("Rust"),
+            Some("# Old Rust\n/target/\n")
+        );
+        assert!(drifts(&mut gitignore, &igs, &required).unwrap().is_empty());
+        assert_eq!(
+            section_state(&gitignore, &igs, "Rust"),
+            SectionState::Outdated
+        );
+
+        assert_eq!(update_sections(&mut gitignore, &igs, &[]).unwrap(), 1);
+        gitignore.write().unwrap();
+
+        let mut gitignore = GitIgnore::open(&path).unwrap();
+
+        assert_eq!(
+            gitignore.section_content("Rust"),
+            Some(GitIgnore::group_content(rust).trim_start_matches('\n'))
+        );
+        assert_eq!(pinned(&path, "Rust").as_ref(), Some(rust));
+        assert!(drifts(&mut gitignore, &igs, &required).unwrap().is_empty());
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn modified_pinned_section_fails_verify_and_is_kept_by_update() {
+        let path = repo("modify");
+        let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+
+        add(&path, &igs, "Rust");
+
+        let content = read_to_string(&path).unwrap();
+        write(&path, content.replace("target/\n", "target/\nextra/\n")).unwrap();
+
+        let mut gitignore = GitIgnore::open(&path).unwrap();
+        let modified = gitignore.section_content("Rust").unwrap().to_string();
+        let drifts = drifts(&mut gitignore, &igs, &["Rust".to_string()]).unwrap();
+
+        assert_eq!(drifts.len(), 1);
+        assert!(drifts[0].to_string().contains("modified locally"));
+
+        assert_eq!(
+            update_sections(&mut gitignore, &igs, &["Rust".to_string()]).unwrap(),
+            0
+        );
+        assert_eq!(gitignore.section_content("Rust"), Some(modified.as_str()));
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn removing_a_missing_group_keeps_its_pin() {
+        let path = repo("remove");
+        let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+
+        let mut gitignore = GitIgnore::open(&path).unwrap();
+        gitignore.pin(igs.get("Python").unwrap()).unwrap();
+        gitignore.add_item("/scratch/");
+        gitignore.write().unwrap();
+
+        let mut gitignore = GitIgnore::open(&path).unwrap();
+        assert!(gitignore.remove_group("Python").is_err());
+        gitignore.add_item("*.local");
+        gitignore.write().unwrap();
+
+        assert!(pinned(&path, "Python").is_some());
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn clear_and_undo_unpin_sections_that_are_gone() {
+        let path = repo("prune");
+        let igs = IgnoreGroups::parse(Target::Gitignore).unwrap();
+
+        add(&path, &igs, "Rust");
+        add(&path, &igs, "Python");
+
+        // What --undo does: back to the file with only Rust.
+        let history = history::History::for_file(&path).unwrap();
+        history.undo(&path, 1).unwrap();
+        GitIgnore::open(&path).unwrap().prune_lock().unwrap();
+
+        assert!(pinned(&path, "Rust").is_some());
+        assert!(pinned(&path, "Python").is_none());
+
+        // What --clear does.
+        history::save(&path, "").unwrap();
+        GitIgnore::open(&path).unwrap().prune_lock().unwrap();
+
+        assert!(pinned(&path, "Rust").is_none());
+
+        remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -1,27 +1,66 @@
+#![allow(clippy::needless_return)]
+
 mod args;
-pub mod r#const;
-mod gitignore;
-mod ignore_groups;
 
-use std::fs::File;
+use args::{Args, Command};
+use d_ig::{
+    explain, export,
+    git_index::Repo,
+    header::HeaderStyle,
+    history::{self, format_timestamp, History},
+    import,
+    manifest::{self, Manifest},
+    output::{paint, ColorChoice},
+    pattern::Pattern,
+    pick, preview,
+    r#const::{
+        BLUE, COLOR, CREATE, CREATE_S, ERROR_FORMAT, GIT_IGNORE, HEADER_STYLE, INTERACTIVE,
+        INTERACTIVE_S, KEEP_GOING, KEEP_GOING_S, ON_EXISTS, PROGRAM_NAME, PROGRAM_VERSION, TARGET,
+        UNTRACK, UNTRACK_S, YELLOW,
+    },
+    search, status,
+    target::Target,
+    wizard, workspace, Error, GitIgnore, IgnoreGroups, OnExists, Plan,
+};
+use std::{env::current_dir, path::Path, process};
+
+/// Runs the command line. `plan_failed` is set when the changes asked for have problems,
+/// which is the only failure `--keep-going` can get past.
+fn run(plan_failed: &mut bool) -> Result<(), Error> {
+    if let Some(color) = Args::global_option(COLOR) {
+        ColorChoice::parse(&color)?.set();
+    }
 
-use args::{Args, Change};
-use gitignore::GitIgnore;
-use ignore_groups::IgnoreGroups;
-use r#const::{FORCE_ADD, FORCE_ADD_S, GIT_IGNORE, PROGRAM_NAME, PROGRAM_VERSION, RED};
+    let target = match Args::global_option(TARGET) {
+        Some(target) => Target::parse(&target)?,
+        None => Target::Gitignore,
+    };
 
-#[derive(Debug)]
-struct Error(String);
+    // Applied to every file written, instead of the style it was read in.
+    let style = match Args::global_option(HEADER_STYLE) {
+        Some(style) => Some(HeaderStyle::parse(&style)?),
+        None => None,
+    };
 
-impl Error {
-    fn log(self) {
-        println!("{RED}ERROR: {}", self.0)
+    if let Some(format) = Args::global_option(ERROR_FORMAT) {
+        if format != "json" && format != "text" {
+            return Err(Error::Usage(format!(
+                "Invalid error format \"{format}\". Expected json or text."
+            )));
+        }
     }
-}
 
-fn run() -> Result<(), Error> {
+    let on_exists = match Args::global_option(ON_EXISTS) {
+        Some(value) => Some(OnExists::parse(&value)?),
+        None => None,
+    };
+
     let args = Args::parse()?;
 
+    if let Some(Command::Explain(paths)) = &args.command {
+        return explain::explain(paths, target);
+    }
+
     if args.version {
         println!("{} ({})", PROGRAM_NAME, PROGRAM_VERSION);
         return Ok(());
@@ -33,63 +72,310 @@ fn run() -> Result<(), Error> {
     }
 
     if args.clear {
-        let path = GitIgnore::path()?;
+        let path = GitIgnore::path(target)?;
+        history::save(&path, "")?;
+
+        return GitIgnore::open(&path)?.prune_lock();
+    }
 
-        return match File::create(path) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(Error(format!("Could not clear {GIT_IGNORE}: {err}"))),
+    if let Some(steps) = args.undo {
+        let path = GitIgnore::path(target)?;
+
+        return match History::for_file(&path) {
+            Some(history) => {
+                history.undo(&path, steps)?;
+                GitIgnore::open(&path)?.prune_lock()
+            }
+            None => Err(Error::NotFound(format!(
+                "{} is not inside a git repository.",
+                target.file_name()
+            ))),
         };
     }
 
-    let igs = IgnoreGroups::parse()?;
+    if args.history {
+        let path = GitIgnore::path(target)?;
+        let entries = match History::for_file(&path) {
+            Some(history) => history.entries()?,
+            None => Vec::new(),
+        };
+
+        if entries.is_empty() {
+            println!("No previous versions of {}.", target.file_name());
+        }
+
+        for (idx, entry) in entries.iter().enumerate() {
+            let lines = std::fs::read_to_string(&entry.path)
+                .map(|x| x.lines().count())
+                .unwrap_or(0);
+
+            println!(
+                "{}  {} UTC  ({lines} lines)",
+                paint(BLUE, format!("{:>3}", idx + 1)),
+                format_timestamp(entry.timestamp)
+            );
+        }
+
+        return Ok(());
+    }
+
+    let mut igs = IgnoreGroups::parse(target)?;
+
+    let manifest = current_dir().ok().and_then(|x| Manifest::find(&x));
+
+    if let Some(path) = manifest {
+        for (alias, group) in Manifest::open(&path, target)?.aliases {
+            igs.add_alias(&alias, &group);
+        }
+    }
+
+    if args.search {
+        return search::search(&igs, &args.list.unwrap_or_default());
+    }
+
+    if args.verify {
+        return status::verify(&igs, &args.list.unwrap_or_default());
+    }
 
     if let Some(lst) = args.list {
+        let mut keys = igs.filter(&lst);
+
+        if let Some(category) = args.category {
+            keys.retain(|x| igs.get(x).is_ok_and(|x| x.category == category));
+        }
+
+        if args.aliases {
+            let aliases: Vec<(String, String)> = igs
+                .aliases()
+                .into_iter()
+                .filter(|(alias, group)| {
+                    lst.is_empty()
+                        || lst.iter().any(|x| {
+                            let x = x.to_lowercase();
+                            alias.to_lowercase().contains(&x) || group.to_lowercase().contains(&x)
+                        })
+                })
+                .collect();
+
+            let width = aliases.iter().map(|(x, _)| x.len()).max().unwrap_or(0);
+
+            for (alias, group) in aliases {
+                println!("{alias:width$}  -> {}", paint(BLUE, group));
+            }
+
+            return Ok(());
+        }
+
         match args.list_exhaustive {
             true => {
-                let keys = igs.filter(&lst);
-
                 for key in keys {
-                    igs.cat(&key);
+                    igs.cat(&key)?;
                 }
 
                 return Ok(());
             }
             false => {
-                let keys = igs.filter(&lst);
                 igs.cat_keys(keys);
                 return Ok(());
             }
         }
     }
 
-    let mut gitignore = GitIgnore::load(args.create)?;
+    match &args.command {
+        Some(Command::Status) => return status::status(&igs),
+        Some(Command::Sync) => return manifest::sync(&igs, style),
+        Some(Command::Preview(groups)) => return preview::preview(&igs, groups),
+        Some(Command::Update(groups)) => return status::update(&igs, groups, style),
+        Some(Command::Import(file)) => return import::import(&igs, file.as_ref(), style),
+        Some(Command::Export {
+            format,
+            stdout,
+            force,
+        }) => return export::export(*format, *stdout, *force, target),
+        _ => {}
+    }
+
+    if let Some(Command::Workspace { yes }) = args.command {
+        return workspace::workspace(&igs, yes, style);
+    }
+
+    let mut changes = args.changes;
+
+    let on_exists = match (args.create, on_exists) {
+        (true, Some(on_exists)) => on_exists,
+        (true, None) => match wizard::on_exists(target)? {
+            Some(on_exists) => on_exists,
+            None => {
+                println!("Nothing was created.");
+                return Ok(());
+            }
+        },
+        (false, _) => OnExists::Fail,
+    };
+
+    if args.interactive {
+        if !args.create {
+            return Err(Error::Usage(format!(
+                "{INTERACTIVE}, {INTERACTIVE_S} can only be used with {CREATE}, {CREATE_S}."
+            )));
+        }
 
-    for change in args.changes {
-        match change {
-            Change::AddG(group) => {
-                if !gitignore.has_group(&group) {
-                    gitignore.add_group(igs.get(&group)?, false, &igs)?
-                } else {
-                    return Err(Error(format!(
-                        "Group '{}' already exists in {}. Perhaps use {}, {}.",
-                        group, GIT_IGNORE, FORCE_ADD, FORCE_ADD_S
-                    )));
+        if target != Target::Gitignore {
+            return Err(Error::Usage(format!(
+                "{INTERACTIVE}, {INTERACTIVE_S} only creates {GIT_IGNORE} files."
+            )));
+        }
+
+        changes = match wizard::create(&igs, changes, on_exists)? {
+            Some(changes) => changes,
+            None => {
+                println!("Nothing was created.");
+                return Ok(());
+            }
+        };
+    }
+
+    let mut gitignore = match args.create {
+        true => GitIgnore::create(on_exists, target)?,
+        false => GitIgnore::load(false, target)?,
+    };
+
+    if let Some(style) = style {
+        gitignore.set_style(style);
+    }
+
+    let existing = gitignore.sections();
+
+    let changes = match args.command {
+        Some(Command::Pick) => pick::pick(&igs, &gitignore)?,
+        _ => changes,
+    };
+
+    if changes.is_empty() && args.command.is_some() {
+        println!("No changes.");
+        return Ok(());
+    }
+
+    let mut plan = Plan::new(changes, &gitignore, &igs);
+
+    if !plan.problems.is_empty() {
+        match args.keep_going {
+            true => {
+                for problem in plan.problems.drain(..) {
+                    println!("{}", paint(YELLOW, format!("SKIPPED: {problem}")));
                 }
             }
-            Change::AddI(item) => gitignore.add_item(&item),
-            Change::RemoveG(group) => gitignore.remove_group(&group)?,
-            Change::RemoveI(item) => gitignore.remove_item(&item),
-            Change::AddGF(group) => gitignore.add_group(igs.get(&group)?, true, &igs)?,
+            false => {
+                *plan_failed = true;
+                return Err(Error::Many(plan.problems));
+            }
         }
     }
 
+    plan.apply(&mut gitignore, &igs)?;
+
+    let added: Vec<Pattern> = gitignore
+        .sections()
+        .iter()
+        .filter(|section| !existing.contains(section))
+        .flat_map(|section| gitignore.section_patterns(section))
+        .collect();
+
+    // Tracked files only matter to git.
+    let tracked = match target {
+        Target::Gitignore => tracked_matches(gitignore.file(), &added)?,
+        Target::Dockerignore => None,
+    };
+
     gitignore.write()?;
 
+    if let Some((repo, files)) = tracked {
+        match args.untrack {
+            true => {
+                repo.untrack(&files)?;
+                println!("Stopped tracking {} file(s).", files.len());
+            }
+            false => println!(
+                "Run with {UNTRACK}, {UNTRACK_S} to stop tracking them:\n    git rm --cached -- {}",
+                files
+                    .iter()
+                    .map(|file| shell_quote(file))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ),
+        }
+    }
+
     return Ok(());
 }
 
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./@+=:,".contains(c);
+
+    return match arg.chars().all(plain) {
+        true => arg.to_string(),
+        false => format!("'{}'", arg.replace('\'', "'\\''")),
+    };
+}
+
+/// Warns about tracked files that newly added patterns would ignore, since git keeps tracking them.
+fn tracked_matches(
+    path: &Path,
+    patterns: &[Pattern],
+) -> Result<Option<(Repo, Vec<String>)>, Error> {
+    let ignore_dir = match path.parent() {
+        Some(dir) => dir,
+        None => return Ok(None),
+    };
+
+    let repo = match Repo::find(ignore_dir) {
+        Some(repo) => repo,
+        None => return Ok(None),
+    };
+
+    let files = repo.tracked_matches(ignore_dir, patterns)?;
+
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    println!(
+        "{}",
+        paint(
+            YELLOW,
+            format!(
+                "WARNING: {} file(s) are tracked but match the added groups:",
+                files.len()
+            )
+        )
+    );
+
+    for file in &files {
+        println!("    {file}");
+    }
+
+    return Ok(Some((repo, files)));
+}
+
 fn main() {
-    if let Err(err) = run() {
-        err.log();
+    let json = Args::global_option(ERROR_FORMAT).is_some_and(|x| x == "json");
+
+    let mut plan_failed = false;
+
+    if let Err(err) = run(&mut plan_failed) {
+        match json {
+            true => eprintln!("{}", err.to_json()),
+            false => {
+                err.log();
+
+                if plan_failed {
+                    eprintln!(
+                        "Use {KEEP_GOING}, {KEEP_GOING_S} to apply the valid changes anyway."
+                    );
+                }
+            }
+        }
+
+        process::exit(err.code());
     }
 }
@@ -0,0 +1,525 @@
+use crate::{
+    gitignore::{cwd, GitIgnore},
+    header::HeaderStyle,
+    ignore_groups::IgnoreGroups,
+    output::paint,
+    plan::{Change, Plan},
+    r#const::{GREEN, MANIFEST, RED},
+    status::{section_state, SectionState},
+    target::Target,
+    Error,
+};
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+/// What a repository declares its ignore file should contain, read from `.d-ig.toml`:
+/// ```toml
+/// groups = ["Rust", "Global/macOS"]
+/// optional = ["Global/JetBrains"]
+/// items = ["/scratch/"]
+///
+/// [dockerignore]
+/// groups = ["Rust", "Secrets"]
+///
+/// [aliases]
+/// web = "Node"
+/// ```
+/// Top level keys describe the .gitignore, a `[dockerignore]` table the .dockerignore.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    /// Groups the file must have.
+    pub groups: Vec<String>,
+    /// Groups the file may have, which are neither added nor removed.
+    pub optional: Vec<String>,
+    /// Single lines of the `_` section, which is left alone when they are not declared.
+    pub items: Option<Vec<String>>,
+    /// Other names for groups, from the `[aliases]` table, which applies to every target.
+    pub aliases: Vec<(String, String)>,
+}
+
+impl Manifest {
+    /// Finds the closest `.d-ig.toml` from `dir` up to the root of its repository.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        let path = dir.join(MANIFEST);
+
+        if path.is_file() {
+            return Some(path);
+        }
+
+        if dir.join(".git").is_dir() {
+            return None;
+        }
+
+        return dir.parent().and_then(Manifest::find);
+    }
+
+    pub fn open(path: &Path, target: Target) -> Result<Manifest, Error> {
+        return match read_to_string(path) {
+            Ok(content) => Manifest::parse(&content, target),
+            Err(err) => Err(Error::Io(format!("Could not read {MANIFEST}"), err)),
+        };
+    }
+
+    /// Reads the part of a manifest that describes the `target` file. Only the subset of
+    /// TOML a manifest needs is understood: tables, comments and keys set to a string or an
+    /// array of strings.
+    pub fn parse(content: &str, target: Target) -> Result<Manifest, Error> {
+        let mut reader = Reader {
+            chars: content.chars().collect(),
+            idx: 0,
+            line: 1,
+        };
+        let mut manifest = Manifest::default();
+        let mut table = String::new();
+        let target = target.file_name().trim_start_matches('.');
+
+        loop {
+            reader.skip_blank();
+
+            match reader.peek() {
+                None => break,
+                Some('[') => {
+                    reader.idx += 1;
+                    table = reader.until(']')?.trim().to_string();
+
+                    if !["gitignore", "dockerignore", "aliases"].contains(&table.as_str()) {
+                        return Err(reader.error(&format!(
+                            "unknown table [{table}], expected [gitignore], [dockerignore] or [aliases]"
+                        )));
+                    }
+
+                    reader.end_of_line()?;
+
+                    continue;
+                }
+                Some(_) => {}
+            }
+
+            let line = reader.line;
+            // Keys may be quoted, for aliases like `"Global/OSX"`.
+            let key = reader
+                .until('=')?
+                .trim()
+                .trim_matches(['"', '\''])
+                .to_string();
+            let values = reader.value()?;
+            reader.end_of_line()?;
+
+            if table == "aliases" {
+                match values.as_slice() {
+                    [group] => manifest.aliases.push((key, group.clone())),
+                    _ => {
+                        reader.line = line;
+                        return Err(reader.error(&format!("alias '{key}' must name one group")));
+                    }
+                }
+
+                continue;
+            }
+
+            let applies = match table.as_str() {
+                "" => target == "gitignore",
+                table => table == target,
+            };
+
+            let field = match key.as_str() {
+                "groups" => &mut manifest.groups,
+                "optional" => &mut manifest.optional,
+                "items" if applies => manifest.items.get_or_insert_with(Vec::new),
+                "items" => continue,
+                _ => {
+                    reader.line = line;
+
+                    return Err(reader.error(&format!(
+                        "unknown key '{key}', expected groups, optional or items"
+                    )));
+                }
+            };
+
+            if applies {
+                field.extend(values);
+            }
+        }
+
+        return Ok(manifest);
+    }
+}
+
+/// A cursor over a manifest that knows which line it is on for errors.
+struct Reader {
+    chars: Vec<char>,
+    idx: usize,
+    line: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.idx).copied();
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.idx += 1;
+
+        if c == '\n' {
+            self.line += 1;
+        }
+
+        return Some(c);
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        return Error::Parse(format!(
+            "Could not parse {MANIFEST} line {}: {msg}.",
+            self.line
+        ));
+    }
+
+    fn skip_comment(&mut self) {
+        while self.peek().is_some_and(|x| x != '\n') {
+            self.idx += 1;
+        }
+    }
+
+    /// Skips whitespace, newlines and comments.
+    fn skip_blank(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                '#' => self.skip_comment(),
+                c if c.is_whitespace() => {
+                    self.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(|x| x == ' ' || x == '\t') {
+            self.idx += 1;
+        }
+    }
+
+    /// Everything up to `end` on the current line, consuming `end`.
+    fn until(&mut self, end: char) -> Result<String, Error> {
+        let mut string = String::new();
+
+        loop {
+            match self.peek() {
+                Some(c) if c == end => {
+                    self.idx += 1;
+                    return Ok(string);
+                }
+                None | Some('\n') => return Err(self.error(&format!("expected '{end}'"))),
+                Some(c) => {
+                    string.push(c);
+                    self.idx += 1;
+                }
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), Error> {
+        self.skip_space();
+
+        if self.peek() == Some('#') {
+            self.skip_comment();
+        }
+
+        return match self.next() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.next() == Some('\n') => Ok(()),
+            Some(c) => Err(self.error(&format!("unexpected '{c}'"))),
+        };
+    }
+
+    /// A string or an array of strings, which may span several lines.
+    fn value(&mut self) -> Result<Vec<String>, Error> {
+        self.skip_space();
+
+        if self.peek() != Some('[') {
+            return Ok(vec![self.string()?]);
+        }
+
+        self.idx += 1;
+        let mut values = Vec::new();
+
+        loop {
+            self.skip_blank();
+
+            if self.peek() == Some(']') {
+                self.idx += 1;
+                return Ok(values);
+            }
+
+            values.push(self.string()?);
+            self.skip_blank();
+
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(values),
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    /// A basic `"..."` string with escapes or a literal `'...'` string.
+    fn string(&mut self) -> Result<String, Error> {
+        let quote = match self.next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.error("expected a string")),
+        };
+
+        let mut string = String::new();
+
+        // Strings end on their own line, so a newline is never consumed and errors name
+        // the line the string is on.
+        loop {
+            let c = match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => c,
+            };
+            self.idx += 1;
+
+            match c {
+                c if c == quote => return Ok(string),
+                '\\' if quote == '"' => {
+                    match self.peek() {
+                        Some('n') => string.push('\n'),
+                        Some('t') => string.push('\t'),
+                        Some(c @ ('"' | '\\')) => string.push(c),
+                        _ => return Err(self.error("unknown escape in string")),
+                    }
+                    self.idx += 1;
+                }
+                c => string.push(c),
+            }
+        }
+    }
+}
+
+/// Rewrites the ignore file next to the closest `.d-ig.toml` to match it. Missing groups
+/// are added, catalog sections that are neither declared nor needed by a declared group
+/// are removed and, when `items` is declared, the `_` section is set to them. Content
+/// outside of d-ig sections is left alone.
+pub fn sync(igs: &IgnoreGroups, style: Option<HeaderStyle>) -> Result<(), Error> {
+    let cwd = cwd()?;
+
+    let path = match Manifest::find(&cwd) {
+        Some(path) => path,
+        None => {
+            return Err(Error::NotFound(format!(
+                "No {MANIFEST} found in this repository."
+            )))
+        }
+    };
+
+    let mut manifest = Manifest::open(&path, igs.target())?;
+
+    for names in [&mut manifest.groups, &mut manifest.optional] {
+        *names = names.iter().map(|x| igs.canonical(x)).collect();
+    }
+    let file = path.with_file_name(igs.target().file_name());
+
+    let mut gitignore = match file.is_file() {
+        true => GitIgnore::open(&file)?,
+        false => GitIgnore::new(&file),
+    };
+
+    if let Some(style) = style {
+        gitignore.set_style(style);
+    }
+
+    let before = gitignore.render();
+    let sections = gitignore.sections();
+
+    // Optional groups and the dependencies of everything kept stay as well.
+    let mut keep = manifest.groups.clone();
+    keep.extend(
+        manifest
+            .optional
+            .iter()
+            .filter(|x| sections.contains(x))
+            .cloned(),
+    );
+    let keep = igs.with_dependencies(&keep)?;
+
+    let mut changes: Vec<Change> = manifest
+        .groups
+        .iter()
+        .filter(|x| !sections.contains(x))
+        .map(|x| Change::AddG(x.clone()))
+        .collect();
+
+    let mut undeclared: Vec<&String> = sections
+        .iter()
+        .filter(|x| igs.get(x).is_ok() && !keep.contains(x))
+        .collect();
+    undeclared.sort();
+
+    changes.extend(undeclared.into_iter().map(|x| Change::RemoveG(x.clone())));
+
+    let plan = Plan::new(changes, &gitignore, igs);
+
+    if !plan.problems.is_empty() {
+        return Err(Error::Many(plan.problems));
+    }
+
+    for change in &plan.changes {
+        match change {
+            Change::AddG(group) => println!("{}", paint(GREEN, format!("+ {group}"))),
+            Change::RemoveG(group) => println!("{}", paint(RED, format!("- {group}"))),
+            _ => {}
+        }
+    }
+
+    plan.apply(&mut gitignore, igs)?;
+
+    // Sections generated before there was a lock are pinned while they match the catalog.
+    for section in gitignore.sections() {
+        if section_state(&gitignore, igs, &section) == SectionState::UpToDate {
+            gitignore.pin(igs.get(&section)?)?;
+        }
+    }
+
+    let items = |gitignore: &GitIgnore| -> Vec<String> {
+        return gitignore.section_content("_").map_or(Vec::new(), |x| {
+            x.lines()
+                .filter(|x| !x.trim().is_empty())
+                .map(|x| x.to_string())
+                .collect()
+        });
+    };
+
+    let custom = items(&gitignore);
+    let declared = manifest.items.as_ref().unwrap_or(&custom);
+
+    if &custom != declared {
+        if !custom.is_empty() {
+            gitignore.remove_group("_")?;
+        }
+
+        for item in declared {
+            gitignore.add_item(item);
+        }
+
+        let synced = items(&gitignore);
+
+        for item in custom.iter().filter(|x| !synced.contains(x)) {
+            println!("{}", paint(RED, format!("- {item}")));
+        }
+
+        for item in synced.iter().filter(|x| !custom.contains(x)) {
+            println!("{}", paint(GREEN, format!("+ {item}")));
+        }
+    }
+
+    if gitignore.render() == before {
+        println!("{} is in sync with {MANIFEST}.", igs.target().file_name());
+
+        // The lock may still have gained pins.
+        if !file.is_file() {
+            return Ok(());
+        }
+    }
+
+    return gitignore.write();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Manifest {
+        return Manifest::parse(content, Target::Gitignore).unwrap();
+    }
+
+    fn error(content: &str) -> String {
+        return match Manifest::parse(content, Target::Gitignore) {
+            Err(Error::Parse(msg)) => msg,
+            other => panic!("expected a parse error, got {other:?}"),
+        };
+    }
+
+    #[test]
+    fn multi_line_arrays() {
+        let manifest = parse(
+            "groups = [\n    \"Rust\",\n    # editors\n    \"Global/macOS\",\n]\noptional = [\n]\n",
+        );
+
+        assert_eq!(manifest.groups, ["Rust", "Global/macOS"]);
+        assert!(manifest.optional.is_empty());
+    }
+
+    #[test]
+    fn comments_after_values() {
+        let manifest = parse(
+            "# the repository\ngroups = [\"Rust\"] # always\nitems = \"*.local\"\t# one item\n",
+        );
+
+        assert_eq!(manifest.groups, ["Rust"]);
+        assert_eq!(manifest.items.unwrap(), ["*.local"]);
+    }
+
+    #[test]
+    fn literal_and_escaped_strings() {
+        let manifest = parse(r#"items = ['C:\temp', "say \"hi\"", "a\\b", '#not-a-comment']"#);
+
+        assert_eq!(
+            manifest.items.unwrap(),
+            [r"C:\temp", r#"say "hi""#, r"a\b", "#not-a-comment"]
+        );
+        assert!(error("items = [\"\\q\"]").contains("unknown escape"));
+    }
+
+    #[test]
+    fn tables_pick_the_target() {
+        let content =
+            "groups = [\"Rust\"]\n\n[dockerignore]\ngroups = [\"Secrets\"]\nitems = [\".env\"]\n";
+
+        assert_eq!(parse(content).groups, ["Rust"]);
+        assert!(parse(content).items.is_none());
+
+        let manifest = Manifest::parse(content, Target::Dockerignore).unwrap();
+        assert_eq!(manifest.groups, ["Secrets"]);
+        assert_eq!(manifest.items.unwrap(), [".env"]);
+    }
+
+    #[test]
+    fn aliases_apply_to_every_target() {
+        let content = "[dockerignore]\ngroups = [\"Node\"]\n\n[aliases]\nweb = \"Node\"\n\"Global/OSX\" = 'Global/macOS'\n";
+
+        for target in [Target::Gitignore, Target::Dockerignore] {
+            let manifest = Manifest::parse(content, target).unwrap();
+
+            assert_eq!(
+                manifest.aliases,
+                [
+                    ("web".to_string(), "Node".to_string()),
+                    ("Global/OSX".to_string(), "Global/macOS".to_string()),
+                ]
+            );
+        }
+
+        assert!(error("[aliases]\nweb = [\"Node\", \"Rust\"]\n").contains("line 2"));
+    }
+
+    #[test]
+    fn unknown_keys_and_tables_give_their_line() {
+        let msg = error("groups = [\"Rust\"]\n\nextra = \"x\"\n");
+        assert!(msg.contains("line 3") && msg.contains("unknown key 'extra'"));
+
+        let msg = error("groups = [\n  \"Rust\",\n]\n[docker]\n");
+        assert!(msg.contains("line 4") && msg.contains("unknown table [docker]"));
+    }
+
+    #[test]
+    fn malformed_lines_give_their_line() {
+        assert!(error("groups = [\"Rust\"] x\n").contains("line 1: unexpected 'x'"));
+        assert!(error("\ngroups = [\"Rust\" \"Node\"]\n").contains("line 2: expected ','"));
+        assert!(error("\n\nitems = \"open\n").contains("line 3: unterminated string"));
+        assert!(error("groups\n").contains("line 1: expected '='"));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    r#const::{BLUE, GREEN, RESET, YELLOW},
+    Error,
+};
+use std::{
+    env,
+    fmt::Display,
+    fs::File,
+    io::{stderr, stdout, IsTerminal},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+static COLOR_CHOICE: AtomicU8 = AtomicU8::new(0);
+
+impl ColorChoice {
+    pub fn parse(value: &str) -> Result<ColorChoice, Error> {
+        match value {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(Error::Usage(format!(
+                "Invalid color choice \"{value}\". Expected auto, always or never."
+            ))),
+        }
+    }
+
+    /// Sets how every later call to [`paint`] and [`paint_err`] behaves.
+    pub fn set(self) {
+        let value = match self {
+            ColorChoice::Auto => 0,
+            ColorChoice::Always => 1,
+            ColorChoice::Never => 2,
+        };
+
+        COLOR_CHOICE.store(value, Ordering::Relaxed);
+    }
+
+    fn get() -> ColorChoice {
+        match COLOR_CHOICE.load(Ordering::Relaxed) {
+            1 => ColorChoice::Always,
+            2 => ColorChoice::Never,
+            _ => ColorChoice::Auto,
+        }
+    }
+}
+
+fn color_enabled(is_terminal: bool) -> bool {
+    match ColorChoice::get() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty());
+            is_terminal && !no_color
+        }
+    }
+}
+
+/// Colors `text` for stdout if colors are enabled.
+pub fn paint(color: &str, text: impl Display) -> String {
+    match color_enabled(stdout().is_terminal()) {
+        true => format!("{color}{text}{RESET}"),
+        false => text.to_string(),
+    }
+}
+
+/// Colors `text` for stderr if colors are enabled.
+pub fn paint_err(color: &str, text: impl Display) -> String {
+    match color_enabled(stderr().is_terminal()) {
+        true => format!("{color}{text}{RESET}"),
+        false => text.to_string(),
+    }
+}
+
+/// Width of the terminal attached to stdout, or `None` if stdout is not a terminal.
+pub fn terminal_width() -> Option<usize> {
+    if !stdout().is_terminal() {
+        return None;
+    }
+
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|x| x.parse().ok()) {
+        return Some(columns);
+    }
+
+    return terminal_size().map(|(_, columns)| columns);
+}
+
+/// Rows and columns of the controlling terminal.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    let size = stty(&["size"])?;
+    let mut size = size.split_whitespace().map(|x| x.parse().ok());
+
+    return Some((size.next()??, size.next()??));
+}
+
+/// Runs `stty` against the controlling terminal, returning its output on success.
+pub fn stty(args: &[&str]) -> Option<String> {
+    let tty = File::open("/dev/tty").ok()?;
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    return Some(String::from_utf8_lossy(&output.stdout).trim().to_string());
+}
+
+/// Lays `items` out in as many columns as fit the terminal, filling each column top to
+/// bottom. Prints one item per line when stdout is not a terminal.
+pub fn columns(items: &[String]) -> String {
+    let width = match terminal_width() {
+        Some(width) => width,
+        None => return items.iter().map(|x| format!("{x}\n")).collect(),
+    };
+
+    let gap = 2;
+    let column_width = items.iter().map(|x| x.chars().count()).max().unwrap_or(0) + gap;
+    let column_count = (width / column_width.max(1)).max(1);
+    let row_count = items.len().div_ceil(column_count);
+
+    let mut string = String::new();
+
+    for row in 0..row_count {
+        let mut line = String::new();
+
+        for column in 0..column_count {
+            let idx = column * row_count + row;
+
+            let item = match items.get(idx) {
+                Some(item) => item,
+                None => break,
+            };
+
+            let color = [GREEN, YELLOW, BLUE][row % 3];
+            let padding = column_width - item.chars().count();
+
+            line += &paint(color, item);
+
+            if items.get(idx + row_count).is_some() {
+                line += &" ".repeat(padding);
+            }
+        }
+
+        string += &line;
+        string += "\n";
+    }
+
+    return string;
+}
@@ -0,0 +1,379 @@
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub source: String,
+    pub negated: bool,
+    pub dir_only: bool,
+    pub anchored: bool,
+    glob: Vec<char>,
+}
+
+impl Pattern {
+    /// Parses a single .gitignore line, returning `None` for blank lines and comments.
+    pub fn parse(line: &str) -> Option<Pattern> {
+        let source = Pattern::trim_trailing_spaces(line);
+
+        if source.is_empty() || source.starts_with('#') {
+            return None;
+        }
+
+        let mut body = source.as_str();
+
+        let negated = body.starts_with('!');
+        if negated || body.starts_with("\\!") || body.starts_with("\\#") {
+            body = &body[1..];
+        }
+
+        let dir_only = body.ends_with('/') && !body.ends_with("\\/");
+        if dir_only {
+            body = body.trim_end_matches('/');
+        }
+
+        if body.is_empty() {
+            return None;
+        }
+
+        let anchored = body.contains('/');
+        let body = body.strip_prefix('/').unwrap_or(body);
+
+        return Some(Pattern {
+            source: source.clone(),
+            negated,
+            dir_only,
+            anchored,
+            glob: body.chars().collect(),
+        });
+    }
+
+    /// The pattern without its negation, leading `/` and trailing `/`.
+    pub fn glob(&self) -> String {
+        return self.glob.iter().collect();
+    }
+
+    /// Parses a single .dockerignore line. Docker matches every pattern from the root of
+    /// the build context and a trailing `/` does not limit it to directories.
+    pub fn parse_docker(line: &str) -> Option<Pattern> {
+        let mut pattern = Pattern::parse(line)?;
+        pattern.anchored = true;
+        pattern.dir_only = false;
+
+        return Some(pattern);
+    }
+
+    fn trim_trailing_spaces(line: &str) -> String {
+        let mut trimmed = line.trim_end_matches(['\n', '\r']).to_string();
+
+        while trimmed.ends_with(' ') && !trimmed.ends_with("\\ ") {
+            trimmed.pop();
+        }
+
+        return trimmed;
+    }
+
+    /// Checks the pattern against a single path relative to the .gitignore directory.
+    /// Parent directories are not considered, see [`check`] for that.
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        let path: Vec<char> = path.trim_matches('/').chars().collect();
+
+        if self.anchored {
+            return wildmatch(&self.glob, &path);
+        }
+
+        let start = path
+            .iter()
+            .rposition(|c| *c == '/')
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+
+        return wildmatch(&self.glob, &path[start..]);
+    }
+}
+
+#[derive(Debug)]
+pub struct Verdict {
+    /// Index of the pattern that decided the outcome.
+    pub decider: Option<usize>,
+    /// Indexes of every pattern that matched the path or one of its parents, in order.
+    pub matched: Vec<usize>,
+    pub ignored: bool,
+}
+
+/// Decides whether `path` is excluded from a Docker build context by `patterns`: the
+/// last pattern matching the path or one of its parents wins, so unlike git a negation
+/// can re-include a file inside an excluded directory.
+pub fn check_docker(patterns: &[Pattern], path: &str, is_dir: bool) -> Verdict {
+    let path = path.trim_matches('/');
+    let mut matched = Vec::new();
+
+    for (p_idx, pattern) in patterns.iter().enumerate() {
+        let parent_matches = path
+            .char_indices()
+            .filter(|(_, c)| *c == '/')
+            .any(|(idx, _)| pattern.matches(&path[..idx], true));
+
+        if parent_matches || pattern.matches(path, is_dir) {
+            matched.push(p_idx);
+        }
+    }
+
+    let decider = matched.last().copied();
+
+    return Verdict {
+        decider,
+        matched,
+        ignored: decider.is_some_and(|x| !patterns[x].negated),
+    };
+}
+
+/// Decides whether `path` is ignored by `patterns` the same way git does: the last
+/// matching pattern wins, and a file inside an ignored directory can not be re-included.
+pub fn check(patterns: &[Pattern], path: &str, is_dir: bool) -> Verdict {
+    let path = path.trim_matches('/');
+    let mut matched = Vec::new();
+
+    for (idx, c) in path.char_indices() {
+        if c != '/' {
+            continue;
+        }
+
+        let prefix = &path[..idx];
+        let mut decider = None;
+
+        for (p_idx, pattern) in patterns.iter().enumerate() {
+            if pattern.matches(prefix, true) {
+                matched.push(p_idx);
+                decider = Some(p_idx);
+            }
+        }
+
+        if let Some(decider) = decider {
+            if !patterns[decider].negated {
+                return Verdict {
+                    decider: Some(decider),
+                    matched,
+                    ignored: true,
+                };
+            }
+        }
+    }
+
+    let mut decider = None;
+
+    for (p_idx, pattern) in patterns.iter().enumerate() {
+        if pattern.matches(path, is_dir) {
+            matched.push(p_idx);
+            decider = Some(p_idx);
+        }
+    }
+
+    let ignored = match decider {
+        Some(decider) => !patterns[decider].negated,
+        None => false,
+    };
+
+    return Verdict {
+        decider,
+        matched,
+        ignored,
+    };
+}
+
+fn wildmatch(glob: &[char], text: &[char]) -> bool {
+    match glob.first() {
+        None => text.is_empty(),
+        Some('*') if glob.get(1) == Some(&'*') => {
+            let rest = &glob[2..];
+
+            if rest.first() == Some(&'/') {
+                let rest = &rest[1..];
+
+                if wildmatch(rest, text) {
+                    return true;
+                }
+
+                for (idx, c) in text.iter().enumerate() {
+                    if *c == '/' && wildmatch(rest, &text[idx + 1..]) {
+                        return true;
+                    }
+                }
+
+                return false;
+            }
+
+            return (0..=text.len()).any(|idx| wildmatch(rest, &text[idx..]));
+        }
+        Some('*') => {
+            let rest = &glob[1..];
+
+            for idx in 0..=text.len() {
+                if wildmatch(rest, &text[idx..]) {
+                    return true;
+                }
+
+                if text.get(idx) == Some(&'/') {
+                    break;
+                }
+            }
+
+            return false;
+        }
+        Some('?') => match text.first() {
+            Some(c) if *c != '/' => wildmatch(&glob[1..], &text[1..]),
+            _ => false,
+        },
+        Some('[') => match (text.first(), class(&glob[1..])) {
+            (Some(c), Some((matches, len))) if *c != '/' => {
+                matches(*c) && wildmatch(&glob[len + 1..], &text[1..])
+            }
+            (_, None) => text.first() == Some(&'[') && wildmatch(&glob[1..], &text[1..]),
+            _ => false,
+        },
+        Some('\\') if glob.len() > 1 => {
+            text.first() == Some(&glob[1]) && wildmatch(&glob[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && wildmatch(&glob[1..], &text[1..]),
+    }
+}
+
+/// Parses a bracket expression (after the opening `[`), returning a predicate
+/// and the number of glob characters consumed including the closing `]`.
+fn class(glob: &[char]) -> Option<(impl Fn(char) -> bool, usize)> {
+    let mut idx = 0;
+    let negated = matches!(glob.first(), Some('!') | Some('^'));
+    if negated {
+        idx += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let start = idx;
+
+    while idx < glob.len() {
+        let c = glob[idx];
+
+        if c == ']' && idx > start {
+            let predicate =
+                move |x: char| ranges.iter().any(|(lo, hi)| *lo <= x && x <= *hi) != negated;
+
+            return Some((predicate, idx + 1));
+        }
+
+        let lo = match c {
+            '\\' if idx + 1 < glob.len() => {
+                idx += 1;
+                glob[idx]
+            }
+            _ => c,
+        };
+
+        if glob.get(idx + 1) == Some(&'-') && glob.get(idx + 2).is_some_and(|x| *x != ']') {
+            ranges.push((lo, glob[idx + 2]));
+            idx += 3;
+        } else {
+            ranges.push((lo, lo));
+            idx += 1;
+        }
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether git would ignore `path` with `lines` as its .gitignore. The expectations
+    /// below were taken from `git check-ignore --no-index`.
+    fn ignored(lines: &[&str], path: &str, is_dir: bool) -> bool {
+        let patterns: Vec<Pattern> = lines.iter().filter_map(|x| Pattern::parse(x)).collect();
+        return check(&patterns, path, is_dir).ignored;
+    }
+
+    #[test]
+    fn leading_double_star_matches_at_any_depth() {
+        let lines = ["**/foo"];
+
+        assert!(ignored(&lines, "foo", false));
+        assert!(ignored(&lines, "a/foo", false));
+        assert!(ignored(&lines, "a/b/foo", false));
+        assert!(ignored(&lines, "a/foo/x", false));
+        assert!(!ignored(&lines, "afoo", false));
+    }
+
+    #[test]
+    fn double_star_directory_matches_only_directories() {
+        let lines = ["**/logs/"];
+
+        assert!(ignored(&lines, "logs", true));
+        assert!(ignored(&lines, "a/logs", true));
+        assert!(ignored(&lines, "a/logs/x.txt", false));
+        assert!(!ignored(&lines, "logs", false));
+    }
+
+    #[test]
+    fn inner_and_trailing_double_star() {
+        let lines = ["a/**/b"];
+
+        assert!(ignored(&lines, "a/b", false));
+        assert!(ignored(&lines, "a/x/b", false));
+        assert!(ignored(&lines, "a/x/y/b", false));
+        assert!(!ignored(&lines, "b", false));
+        assert!(!ignored(&lines, "x/a/b", false));
+
+        let lines = ["abc/**"];
+
+        assert!(ignored(&lines, "abc/x", false));
+        assert!(ignored(&lines, "abc/x/y", false));
+        assert!(!ignored(&lines, "abc", false));
+    }
+
+    #[test]
+    fn anchored_rules_match_from_the_root() {
+        let lines = ["/build"];
+
+        assert!(ignored(&lines, "build", false));
+        assert!(ignored(&lines, "build/x", false));
+        assert!(!ignored(&lines, "a/build", false));
+
+        // A slash in the middle anchors the rule too.
+        let lines = ["doc/frotz"];
+
+        assert!(ignored(&lines, "doc/frotz", false));
+        assert!(!ignored(&lines, "a/doc/frotz", false));
+    }
+
+    #[test]
+    fn directory_only_rules() {
+        let lines = ["out/"];
+
+        assert!(ignored(&lines, "out", true));
+        assert!(ignored(&lines, "a/out", true));
+        assert!(ignored(&lines, "a/out/f", false));
+        assert!(!ignored(&lines, "out", false));
+    }
+
+    #[test]
+    fn negation_re_includes_files() {
+        let lines = ["*.log", "!keep.log"];
+
+        assert!(ignored(&lines, "a.log", false));
+        assert!(!ignored(&lines, "keep.log", false));
+        assert!(!ignored(&lines, "d/keep.log", false));
+    }
+
+    #[test]
+    fn negation_under_excluded_parent_has_no_effect() {
+        let lines = ["logs/", "!logs/keep.txt"];
+
+        assert!(ignored(&lines, "logs/keep.txt", false));
+        assert!(ignored(&lines, "logs/other.txt", false));
+
+        // Excluding the contents instead of the directory lets a negation through.
+        let lines = ["logs/*", "!logs/keep/"];
+
+        assert!(!ignored(&lines, "logs/keep/f", false));
+        assert!(ignored(&lines, "logs/x", false));
+    }
+}
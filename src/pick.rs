@@ -0,0 +1,302 @@
+use crate::{
+    gitignore::GitIgnore,
+    ignore_groups::{IgnoreGroups, IgnoreItem},
+    output::{paint, stty, terminal_size},
+    plan::Change,
+    r#const::{BLUE, GREEN, YELLOW},
+    Error,
+};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{stdout, Read, Write},
+};
+
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const INVERT: &str = "\x1b[7m";
+const NORMAL: &str = "\x1b[27m";
+
+enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Toggle,
+    Confirm,
+    Cancel,
+    Backspace,
+    Char(char),
+    Other,
+}
+
+/// Puts the terminal in raw mode on an alternate screen and restores it when dropped.
+struct Screen {
+    saved: String,
+}
+
+impl Screen {
+    fn enter() -> Result<Screen, Error> {
+        let saved = match stty(&["-g"]) {
+            Some(saved) => saved,
+            None => {
+                return Err(Error::Usage(
+                    "pick needs an interactive terminal.".to_string(),
+                ))
+            }
+        };
+
+        // Reads return after a tenth of a second so a lone escape can be told apart from a sequence.
+        stty(&["raw", "-echo", "min", "0", "time", "1"]);
+        print!("{ENTER_SCREEN}");
+        let _ = stdout().flush();
+
+        return Ok(Screen { saved });
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        print!("{LEAVE_SCREEN}");
+        let _ = stdout().flush();
+        stty(&[&self.saved]);
+    }
+}
+
+struct Picker<'a> {
+    igs: &'a IgnoreGroups,
+    filter: String,
+    keys: Vec<String>,
+    selected: usize,
+    offset: usize,
+    checked: HashSet<String>,
+}
+
+/// Lets the user check and uncheck groups, returning the changes needed to match the selection.
+pub fn pick(igs: &IgnoreGroups, gitignore: &GitIgnore) -> Result<Vec<Change>, Error> {
+    let initial: HashSet<String> = gitignore
+        .sections()
+        .into_iter()
+        .filter(|x| igs.get(x).is_ok())
+        .collect();
+
+    let mut picker = Picker {
+        igs,
+        filter: String::new(),
+        keys: igs.filter(&Vec::new()),
+        selected: 0,
+        offset: 0,
+        checked: initial.clone(),
+    };
+
+    let mut tty = match File::open("/dev/tty") {
+        Ok(tty) => tty,
+        Err(err) => return Err(Error::Io("Could not open terminal".to_string(), err)),
+    };
+
+    let confirmed = {
+        let _screen = Screen::enter()?;
+        picker.run(&mut tty)?
+    };
+
+    if !confirmed {
+        return Ok(Vec::new());
+    }
+
+    let mut changes = Vec::new();
+
+    let mut added: Vec<&String> = picker.checked.difference(&initial).collect();
+    added.sort();
+    changes.extend(added.into_iter().map(|x| Change::AddG(x.clone())));
+
+    let mut removed: Vec<&String> = initial.difference(&picker.checked).collect();
+    removed.sort();
+    changes.extend(removed.into_iter().map(|x| Change::RemoveG(x.clone())));
+
+    return Ok(changes);
+}
+
+impl Picker<'_> {
+    fn run(&mut self, tty: &mut File) -> Result<bool, Error> {
+        let mut drawn = None;
+
+        loop {
+            let (rows, columns) = terminal_size().unwrap_or((24, 80));
+            let height = rows.saturating_sub(2).max(1);
+
+            // Reads time out several times a second, so only redraw after input or a resize.
+            if drawn != Some((rows, columns)) {
+                self.draw(columns, height);
+                drawn = Some((rows, columns));
+            }
+
+            let keys = read_keys(tty)?;
+
+            if keys.is_empty() {
+                continue;
+            }
+
+            for key in keys {
+                match key {
+                    Key::Up => self.selected = self.selected.saturating_sub(1),
+                    Key::Down => self.selected += 1,
+                    Key::PageUp => self.selected = self.selected.saturating_sub(height),
+                    Key::PageDown => self.selected += height,
+                    Key::Toggle => {
+                        if let Some(key) = self.keys.get(self.selected) {
+                            if !self.checked.remove(key) {
+                                self.checked.insert(key.clone());
+                            }
+                        }
+                    }
+                    Key::Confirm => return Ok(true),
+                    Key::Cancel => return Ok(false),
+                    Key::Backspace => {
+                        self.filter.pop();
+                        self.refilter();
+                    }
+                    Key::Char(c) => {
+                        self.filter.push(c);
+                        self.refilter();
+                    }
+                    Key::Other => {}
+                }
+
+                self.selected = self.selected.min(self.keys.len().saturating_sub(1));
+            }
+
+            if self.selected < self.offset {
+                self.offset = self.selected;
+            } else if self.selected >= self.offset + height {
+                self.offset = self.selected + 1 - height;
+            }
+
+            drawn = None;
+        }
+    }
+
+    fn refilter(&mut self) {
+        self.keys = self.igs.filter(&vec![self.filter.clone()]);
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    fn draw(&self, columns: usize, height: usize) {
+        let list_width = (columns / 2).min(44);
+        let preview_width = columns.saturating_sub(list_width + 3);
+
+        let mut frame = String::from(CLEAR);
+
+        let header = format!(" d-ig pick  filter: {}_", self.filter);
+        frame += &format!("{INVERT}{}{NORMAL}\r\n", fit(&header, columns));
+
+        let preview = match self.keys.get(self.selected) {
+            Some(key) => self.preview(key),
+            None => Vec::new(),
+        };
+
+        for row in 0..height {
+            let idx = self.offset + row;
+
+            let entry = match self.keys.get(idx) {
+                Some(key) => {
+                    let mark = match self.checked.contains(key) {
+                        true => "[x]",
+                        false => "[ ]",
+                    };
+
+                    let entry = fit(&format!("{mark} {key}"), list_width);
+
+                    match idx == self.selected {
+                        true => format!("{INVERT}{entry}{NORMAL}"),
+                        false => entry,
+                    }
+                }
+                None => " ".repeat(list_width),
+            };
+
+            let (color, line) = match preview.get(row) {
+                Some((color, line)) => (*color, fit(line, preview_width)),
+                None => ("", String::new()),
+            };
+
+            frame += &format!("{entry} | {}\r\n", paint(color, line.trim_end()));
+        }
+
+        let footer = format!(
+            " up/down move  space toggle  enter apply  esc cancel  {} selected",
+            self.checked.len()
+        );
+        frame += &format!("{INVERT}{}{NORMAL}", fit(&footer, columns));
+
+        print!("{frame}");
+        let _ = stdout().flush();
+    }
+
+    fn preview(&self, key: &str) -> Vec<(&'static str, String)> {
+        let group = match self.igs.get(key) {
+            Ok(group) => group,
+            Err(_) => return Vec::new(),
+        };
+
+        return group
+            .items
+            .iter()
+            .map(|item| match item {
+                IgnoreItem::Item(item) => (GREEN, item.clone()),
+                IgnoreItem::Comment(comment) => (YELLOW, format!("# {comment}")),
+                IgnoreItem::Dependency(dep) => (BLUE, format!("Dependency({dep})")),
+            })
+            .collect();
+    }
+}
+
+/// Pads or truncates `text` to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted += &" ".repeat(width - len);
+
+    return fitted;
+}
+
+/// Reads whatever is waiting on the terminal, which may be several keys at once when typing fast or pasting.
+fn read_keys(tty: &mut File) -> Result<Vec<Key>, Error> {
+    let mut buffer = [0; 64];
+
+    let len = match tty.read(&mut buffer) {
+        Ok(len) => len,
+        Err(err) => return Err(Error::Io("Could not read from terminal".to_string(), err)),
+    };
+
+    let mut bytes = &buffer[..len];
+    let mut keys = Vec::new();
+
+    while !bytes.is_empty() {
+        let (key, used) = match bytes {
+            [27, b'[', b'A', ..] | [27, b'O', b'A', ..] => (Key::Up, 3),
+            [27, b'[', b'B', ..] | [27, b'O', b'B', ..] => (Key::Down, 3),
+            [27, b'[', b'5', b'~', ..] => (Key::PageUp, 4),
+            [27, b'[', b'6', b'~', ..] => (Key::PageDown, 4),
+            [27, b'[', rest @ ..] => {
+                // Skip any other escape sequence up to its final byte.
+                let end = rest.iter().position(|x| (0x40..=0x7e).contains(x));
+                (Key::Other, end.map(|x| x + 3).unwrap_or(bytes.len()))
+            }
+            [27, ..] | [3, ..] | [17, ..] => (Key::Cancel, 1),
+            [16, ..] => (Key::Up, 1),
+            [14, ..] => (Key::Down, 1),
+            [b' ', ..] | [b'\t', ..] => (Key::Toggle, 1),
+            [b'\r', ..] | [b'\n', ..] => (Key::Confirm, 1),
+            [127, ..] | [8, ..] => (Key::Backspace, 1),
+            [c, ..] if c.is_ascii_graphic() => (Key::Char(*c as char), 1),
+            _ => (Key::Other, 1),
+        };
+
+        keys.push(key);
+        bytes = &bytes[used..];
+    }
+
+    return Ok(keys);
+}
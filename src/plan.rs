@@ -0,0 +1,102 @@
+use crate::{
+    gitignore::GitIgnore,
+    ignore_groups::IgnoreGroups,
+    r#const::{FORCE_ADD, FORCE_ADD_S},
+    Error,
+};
+use std::collections::HashSet;
+
+/// A single requested edit to a .gitignore.
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// Adds a catalog group.
+    AddG(String),
+    /// Adds a catalog group, replacing it if it already exists.
+    AddGF(String),
+    /// Adds a single item to the `_` section.
+    AddI(String),
+    /// Removes a section.
+    RemoveG(String),
+    /// Removes a single item from every section.
+    RemoveI(String),
+}
+
+/// Changes resolved against the catalog and the current file before anything is applied.
+pub struct Plan {
+    pub changes: Vec<Change>,
+    pub problems: Vec<Error>,
+}
+
+impl Plan {
+    pub fn new(changes: Vec<Change>, gitignore: &GitIgnore, igs: &IgnoreGroups) -> Plan {
+        let mut sections: HashSet<String> = gitignore.sections().into_iter().collect();
+        let mut valid = Vec::new();
+        let mut problems = Vec::new();
+
+        for change in changes {
+            // Aliases are planned under the name of the group they stand for.
+            let change = match change {
+                Change::AddG(group) => Change::AddG(igs.canonical(&group)),
+                Change::AddGF(group) => Change::AddGF(igs.canonical(&group)),
+                Change::RemoveG(group) => Change::RemoveG(igs.canonical(&group)),
+                change => change,
+            };
+
+            let problem = match &change {
+                Change::AddG(group) => match igs.validate(group) {
+                    Err(err) => Some(err),
+                    Ok(_) if sections.contains(group) => Some(Error::AlreadyPresent(format!(
+                        "Group '{}' already exists in {}. Perhaps use {}, {}.",
+                        group,
+                        gitignore.target().file_name(),
+                        FORCE_ADD,
+                        FORCE_ADD_S
+                    ))),
+                    Ok(_) => {
+                        sections.insert(group.clone());
+                        None
+                    }
+                },
+                Change::AddGF(group) => match igs.validate(group) {
+                    Err(err) => Some(err),
+                    Ok(_) => {
+                        sections.insert(group.clone());
+                        None
+                    }
+                },
+                Change::RemoveG(group) => match sections.remove(group) {
+                    true => None,
+                    false => Some(Error::NotFound(format!(
+                        "{} does not have ignore group '{group}'.",
+                        gitignore.target().file_name()
+                    ))),
+                },
+                Change::AddI(_) | Change::RemoveI(_) => None,
+            };
+
+            match problem {
+                Some(problem) => problems.push(problem),
+                None => valid.push(change),
+            }
+        }
+
+        return Plan {
+            changes: valid,
+            problems,
+        };
+    }
+
+    pub fn apply(self, gitignore: &mut GitIgnore, igs: &IgnoreGroups) -> Result<(), Error> {
+        for change in self.changes {
+            match change {
+                Change::AddG(group) => gitignore.add_group(igs.get(&group)?, false, igs)?,
+                Change::AddGF(group) => gitignore.add_group(igs.get(&group)?, true, igs)?,
+                Change::AddI(item) => gitignore.add_item(&item),
+                Change::RemoveG(group) => gitignore.remove_group(&group)?,
+                Change::RemoveI(item) => gitignore.remove_item(&item),
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{
+    detect::entries,
+    git_index::Repo,
+    gitignore::GitIgnore,
+    ignore_groups::IgnoreGroups,
+    output::paint,
+    pattern::{check, check_docker, Pattern},
+    r#const::{BLUE, GREEN, PREVIEW, UNMANAGED, YELLOW},
+    target::Target,
+    Error,
+};
+use std::{collections::HashSet, path::Path};
+
+/// Shows what adding `groups` would change without writing anything: the sections that
+/// would be added, dependencies included, rules other sections already have, and the
+/// files in the working tree that would become ignored.
+pub fn preview(igs: &IgnoreGroups, groups: &[String]) -> Result<(), Error> {
+    if groups.is_empty() {
+        return Err(Error::Usage(format!(
+            "{PREVIEW} requires at least one ignore group."
+        )));
+    }
+
+    let target = igs.target();
+    let path = GitIgnore::path_or_build_location(target)?;
+
+    let open = || match path.is_file() {
+        true => GitIgnore::open(&path),
+        false => Ok(GitIgnore::new(&path)),
+    };
+
+    let before = open()?;
+    let mut after = open()?;
+
+    for group in groups {
+        igs.validate(group)?;
+        after.add_group(igs.get(group)?, false, igs)?;
+    }
+
+    let existing = before.sections();
+    let mut added: Vec<String> = after
+        .sections()
+        .into_iter()
+        .filter(|x| !existing.contains(x))
+        .collect();
+    added.sort();
+
+    let file_name = target.file_name();
+
+    if added.is_empty() {
+        println!("{file_name} already has {}.", groups.join(", "));
+        return Ok(());
+    }
+
+    for section in &added {
+        let origin = match after
+            .section_meta(section)
+            .and_then(|x| x.dependency_of.as_ref())
+        {
+            Some(parent) => format!("  (dependency of {parent})"),
+            None => String::new(),
+        };
+
+        println!("{}{origin}", paint(GREEN, format!("+ {section}")));
+
+        for line in after
+            .section_content(section)
+            .unwrap_or("")
+            .trim_matches('\n')
+            .lines()
+        {
+            match line.is_empty() {
+                true => println!(),
+                false => println!("    {line}"),
+            }
+        }
+
+        println!();
+    }
+
+    let mut others = existing.clone();
+    others.sort();
+    others.insert(0, UNMANAGED.to_string());
+
+    let mut duplicates = Vec::new();
+
+    for section in &added {
+        for pattern in after.section_patterns(section) {
+            let other = others.iter().find(|x| {
+                before
+                    .section_patterns(x)
+                    .iter()
+                    .any(|x| x.source == pattern.source)
+            });
+
+            match other {
+                Some(other) if other == UNMANAGED => duplicates.push(format!(
+                    "{} ({section}) is already outside of d-ig sections",
+                    pattern.source
+                )),
+                Some(other) => duplicates.push(format!(
+                    "{} ({section}) is already in {other}",
+                    pattern.source
+                )),
+                None => {}
+            }
+        }
+    }
+
+    if !duplicates.is_empty() {
+        println!("{}", paint(YELLOW, "Rules already present:"));
+
+        for duplicate in duplicates {
+            println!("    {duplicate}");
+        }
+
+        println!();
+    }
+
+    let root = match path.parent() {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+
+    let old = patterns(&before);
+    let new = patterns(&after);
+
+    // Only git tracks files, which stay tracked after they are ignored.
+    let tracked = match (target, Repo::find(root)) {
+        (Target::Gitignore, Some(repo)) => {
+            let prefix = root
+                .strip_prefix(&repo.root)
+                .map(|x| x.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+
+            repo.tracked_files()?
+                .into_iter()
+                .filter_map(|x| match prefix.is_empty() {
+                    true => Some(x),
+                    false => x.strip_prefix(&format!("{prefix}/")).map(|x| x.to_string()),
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    let tracked_ignored: Vec<&String> = tracked
+        .iter()
+        .filter(|x| !ignored(target, &old, x, false) && ignored(target, &new, x, false))
+        .collect();
+
+    let tracked = Tracked::new(&tracked);
+    let mut untracked_ignored = Vec::new();
+    newly_ignored(
+        target,
+        root,
+        "",
+        &old,
+        &new,
+        &tracked,
+        &mut untracked_ignored,
+    );
+
+    if tracked_ignored.is_empty() && untracked_ignored.is_empty() {
+        println!("No files in {} would become ignored.", root.display());
+        return Ok(());
+    }
+
+    if !tracked_ignored.is_empty() {
+        println!(
+            "{}",
+            paint(
+                YELLOW,
+                format!(
+                    "{} tracked file(s) would match, git keeps tracking them:",
+                    tracked_ignored.len()
+                )
+            )
+        );
+
+        for file in tracked_ignored {
+            println!("    {file}");
+        }
+    }
+
+    if !untracked_ignored.is_empty() {
+        println!(
+            "{}",
+            paint(
+                BLUE,
+                format!("{} path(s) would become ignored:", untracked_ignored.len())
+            )
+        );
+
+        for file in untracked_ignored {
+            println!("    {file}");
+        }
+    }
+
+    return Ok(());
+}
+
+/// The rules of `gitignore` in the order they would be written.
+fn patterns(gitignore: &GitIgnore) -> Vec<Pattern> {
+    return gitignore
+        .render()
+        .lines()
+        .filter_map(|x| gitignore.target().pattern(x))
+        .collect();
+}
+
+fn ignored(target: Target, patterns: &[Pattern], path: &str, is_dir: bool) -> bool {
+    return match target {
+        Target::Gitignore => check(patterns, path, is_dir).ignored,
+        Target::Dockerignore => check_docker(patterns, path, is_dir).ignored,
+    };
+}
+
+/// Tracked files and every directory holding one, built once before walking the tree.
+struct Tracked<'a> {
+    files: HashSet<&'a str>,
+    dirs: HashSet<&'a str>,
+}
+
+impl Tracked<'_> {
+    fn new(files: &[String]) -> Tracked<'_> {
+        let mut dirs = HashSet::new();
+
+        for file in files {
+            let mut path = file.as_str();
+
+            while let Some((parent, _)) = path.rsplit_once('/') {
+                // The parents of a directory already seen are in the set too.
+                if !dirs.insert(parent) {
+                    break;
+                }
+
+                path = parent;
+            }
+        }
+
+        return Tracked {
+            files: files.iter().map(|x| x.as_str()).collect(),
+            dirs,
+        };
+    }
+}
+
+/// Collects untracked paths under `dir` that `new` ignores and `old` does not. A newly
+/// ignored directory without tracked files is listed once instead of file by file.
+fn newly_ignored(
+    target: Target,
+    root: &Path,
+    dir: &str,
+    old: &[Pattern],
+    new: &[Pattern],
+    tracked: &Tracked,
+    found: &mut Vec<String>,
+) {
+    let mut names = entries(&root.join(dir));
+    names.sort();
+
+    for name in names {
+        if name == ".git" {
+            continue;
+        }
+
+        let path = match dir.is_empty() {
+            true => name,
+            false => format!("{dir}/{name}"),
+        };
+
+        let full = root.join(&path);
+        let is_dir = full.is_dir() && !full.is_symlink();
+
+        if ignored(target, old, &path, is_dir) {
+            continue;
+        }
+
+        let has_tracked = match is_dir {
+            true => tracked.dirs.contains(path.as_str()),
+            false => tracked.files.contains(path.as_str()),
+        };
+
+        match (ignored(target, new, &path, is_dir), is_dir, has_tracked) {
+            (true, true, false) => found.push(format!("{path}/")),
+            (true, false, false) => found.push(path),
+            (_, true, _) => newly_ignored(target, root, &path, old, new, tracked, found),
+            (_, false, _) => {}
+        }
+    }
+}
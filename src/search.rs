@@ -0,0 +1,156 @@
+use crate::{
+    gitignore::GitIgnore,
+    ignore_groups::IgnoreGroups,
+    output::paint,
+    pattern::Pattern,
+    r#const::{BLUE, GREEN, SEARCH, SEARCH_S},
+    Error,
+};
+
+/// Lines of context shown around each match.
+const CONTEXT: usize = 1;
+
+/// A catalog group with the lines of its content that match a search.
+#[derive(Debug)]
+pub struct Hit {
+    pub group: String,
+    pub score: usize,
+    /// Content lines of the group, as it would be generated.
+    pub lines: Vec<String>,
+    /// Indices into `lines` that matched.
+    pub matches: Vec<usize>,
+}
+
+/// How well a single content line matches `term`, which is lowercase. A rule that is the
+/// term itself ranks highest, then rules that would ignore it, rules containing it and
+/// last comments.
+fn score(line: &str, term: &str) -> usize {
+    let line = line.trim().to_lowercase();
+
+    if let Some(comment) = line.strip_prefix('#') {
+        return match comment.contains(term) {
+            true => 1,
+            false => 0,
+        };
+    }
+
+    let bare = |x: &str| x.trim_start_matches("**/").trim_matches('/').to_string();
+
+    if line == term || bare(&line) == bare(term) {
+        return 20;
+    }
+
+    if line.contains(term) {
+        return 5;
+    }
+
+    // `*.pyc` is not written in a group that ignores `*.py[cod]`, but the rule matches it.
+    // Rules like `*` in allow-list templates match anything and say nothing about it.
+    let path = term.trim_start_matches("**/").trim_matches('/');
+    let ignores = Pattern::parse(&line).is_some_and(|x| {
+        !x.negated
+            && !x.glob().chars().all(|c| c == '*' || c == '/')
+            && !path.is_empty()
+            && x.matches(path, term.ends_with('/'))
+    });
+
+    return match ignores {
+        true => 10,
+        false => 0,
+    };
+}
+
+/// Finds the catalog groups whose rules or comments contain any of `terms`, best first.
+pub fn find(igs: &IgnoreGroups, terms: &[String]) -> Vec<Hit> {
+    let terms: Vec<String> = terms.iter().map(|x| x.trim().to_lowercase()).collect();
+    let mut hits = Vec::new();
+
+    for name in igs.filter(&Vec::new()) {
+        let group = match igs.get(&name) {
+            Ok(group) => group,
+            Err(_) => continue,
+        };
+
+        let lines: Vec<String> = GitIgnore::group_content(group)
+            .lines()
+            .map(|x| x.to_string())
+            .collect();
+
+        let mut score_sum = 0;
+        let mut matches = Vec::new();
+
+        for (idx, line) in lines.iter().enumerate() {
+            let line_score: usize = terms.iter().map(|x| score(line, x)).sum();
+
+            if line_score > 0 {
+                score_sum += line_score;
+                matches.push(idx);
+            }
+        }
+
+        if !matches.is_empty() {
+            hits.push(Hit {
+                group: name,
+                score: score_sum,
+                lines,
+                matches,
+            });
+        }
+    }
+
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.group.cmp(&b.group)));
+
+    return hits;
+}
+
+/// Prints every group matching `terms` with its matching lines in context.
+pub fn search(igs: &IgnoreGroups, terms: &[String]) -> Result<(), Error> {
+    if terms.is_empty() {
+        return Err(Error::Usage(format!(
+            "{SEARCH}, {SEARCH_S} requires at least one pattern."
+        )));
+    }
+
+    let hits = find(igs, terms);
+
+    if hits.is_empty() {
+        println!("No ignore group contains {}.", terms.join(" or "));
+        return Ok(());
+    }
+
+    for hit in hits {
+        println!(
+            "{}  ({} matching line(s))",
+            paint(BLUE, &hit.group),
+            hit.matches.len()
+        );
+
+        let mut last = None;
+
+        for (idx, line) in hit.lines.iter().enumerate() {
+            let near = hit
+                .matches
+                .iter()
+                .any(|x| idx + CONTEXT >= *x && idx <= x + CONTEXT);
+
+            if !near || line.is_empty() {
+                continue;
+            }
+
+            if last.is_some_and(|x| x + 1 < idx) {
+                println!("    ...");
+            }
+
+            match hit.matches.contains(&idx) {
+                true => println!("{}", paint(GREEN, format!("  > {line}"))),
+                false => println!("    {line}"),
+            }
+
+            last = Some(idx);
+        }
+
+        println!();
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,261 @@
+use crate::{
+    gitignore::{content_hash, GitIgnore},
+    header::HeaderStyle,
+    ignore_groups::IgnoreGroups,
+    manifest::Manifest,
+    output::paint,
+    r#const::{BLUE, FORCE_ADD, FORCE_ADD_S, GREEN, MANIFEST, RED, UNMANAGED, YELLOW},
+    Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SectionState {
+    /// Matches what the catalog generated and still generates.
+    UpToDate,
+    /// Edited since d-ig generated it.
+    Modified,
+    /// The catalog has changed since the section was generated.
+    Outdated,
+    ModifiedAndOutdated,
+    /// Written before headers carried metadata, so edits can't be told from catalog changes.
+    NoMetadata,
+    /// Not a catalog group, such as the `_` section of single items.
+    Custom,
+}
+
+impl SectionState {
+    pub fn describe(self) -> String {
+        match self {
+            SectionState::UpToDate => paint(GREEN, "up to date"),
+            SectionState::Modified => paint(YELLOW, "modified locally"),
+            SectionState::Outdated => paint(BLUE, "update available"),
+            SectionState::ModifiedAndOutdated => paint(RED, "modified locally, update available"),
+            SectionState::NoMetadata => paint(YELLOW, "no metadata"),
+            SectionState::Custom => "custom".to_string(),
+        }
+    }
+}
+
+/// Compares `section` with the metadata in its header and the catalog.
+pub fn section_state(gitignore: &GitIgnore, igs: &IgnoreGroups, section: &str) -> SectionState {
+    let group = match igs.get(section) {
+        Ok(group) => group,
+        Err(_) => return SectionState::Custom,
+    };
+
+    let meta = match gitignore.section_meta(section) {
+        Some(meta) => meta,
+        None => return SectionState::NoMetadata,
+    };
+
+    let current = content_hash(gitignore.section_content(section).unwrap_or(""));
+    let catalog = content_hash(&GitIgnore::group_content(group));
+
+    return match (current != meta.hash, catalog != meta.hash) {
+        (false, false) => SectionState::UpToDate,
+        (true, false) => SectionState::Modified,
+        (false, true) => SectionState::Outdated,
+        (true, true) => SectionState::ModifiedAndOutdated,
+    };
+}
+
+/// Prints every section of the .gitignore with how it compares to the catalog.
+pub fn status(igs: &IgnoreGroups) -> Result<(), Error> {
+    let gitignore = GitIgnore::load(false, igs.target())?;
+
+    let mut sections = gitignore.sections();
+    sections.sort();
+
+    if sections.is_empty() {
+        println!("{} has no d-ig sections.", igs.target().file_name());
+    }
+
+    for section in sections {
+        let state = section_state(&gitignore, igs, &section);
+
+        let origin = match gitignore.section_meta(&section) {
+            Some(meta) => match &meta.dependency_of {
+                Some(parent) => format!("  (catalog {}, dependency of {parent})", meta.catalog),
+                None => format!("  (catalog {})", meta.catalog),
+            },
+            None => String::new(),
+        };
+
+        println!("{section}: {}{origin}", state.describe());
+    }
+
+    return Ok(());
+}
+
+/// Regenerates outdated sections, or only `groups` if any are given. Sections edited
+/// since they were generated are left alone.
+pub fn update(
+    igs: &IgnoreGroups,
+    groups: &[String],
+    style: Option<HeaderStyle>,
+) -> Result<(), Error> {
+    let mut gitignore = GitIgnore::load(false, igs.target())?;
+
+    if let Some(style) = style {
+        gitignore.set_style(style);
+    }
+
+    if update_sections(&mut gitignore, igs, groups)? == 0 {
+        println!("Nothing to update.");
+        return Ok(());
+    }
+
+    return gitignore.write();
+}
+
+/// Regenerates the sections [`update`] would in `gitignore`, returning how many changed.
+pub(crate) fn update_sections(
+    gitignore: &mut GitIgnore,
+    igs: &IgnoreGroups,
+    groups: &[String],
+) -> Result<usize, Error> {
+    let mut sections = match groups.is_empty() {
+        true => gitignore.sections(),
+        false => groups.iter().map(|x| igs.canonical(x)).collect(),
+    };
+    sections.sort();
+
+    let existing = gitignore.sections();
+    let mut updated = 0;
+
+    for section in sections {
+        if !existing.contains(&section) {
+            return Err(Error::NotFound(format!(
+                "{} does not have ignore group '{section}'.",
+                igs.target().file_name()
+            )));
+        }
+
+        let state = section_state(gitignore, igs, &section);
+
+        match state {
+            SectionState::UpToDate | SectionState::Custom => continue,
+            SectionState::Outdated => {
+                gitignore.update_group(igs.get(&section)?)?;
+                println!("{section}: {}", paint(GREEN, "updated"));
+                updated += 1;
+            }
+            SectionState::NoMetadata => {
+                let group = igs.get(&section)?;
+                let current = content_hash(gitignore.section_content(&section).unwrap_or(""));
+
+                // An old header over untouched catalog content only needs its metadata.
+                if current == content_hash(&GitIgnore::group_content(group)) {
+                    gitignore.update_group(group)?;
+                    println!("{section}: {}", paint(GREEN, "metadata recorded"));
+                    updated += 1;
+                    continue;
+                }
+
+                println!(
+                    "{section}: kept, it has no metadata to tell local edits from catalog changes. Use {FORCE_ADD}, {FORCE_ADD_S} {section} to regenerate it."
+                );
+            }
+            SectionState::Modified | SectionState::ModifiedAndOutdated => {
+                if state == SectionState::Modified && groups.is_empty() {
+                    continue;
+                }
+
+                println!(
+                    "{section}: kept, it was modified locally. Use {FORCE_ADD}, {FORCE_ADD_S} {section} to regenerate it."
+                );
+            }
+        }
+    }
+
+    return Ok(updated);
+}
+
+/// Checks without writing that `groups` and their dependencies are present, match the
+/// catalog and aren't repeated outside of d-ig sections. Without `groups` the ones declared
+/// in a `.d-ig.toml` next to the file are checked, or every catalog section if there is none.
+pub fn verify(igs: &IgnoreGroups, groups: &[String]) -> Result<(), Error> {
+    let mut gitignore = GitIgnore::load(false, igs.target())?;
+
+    let manifest = gitignore.file().with_file_name(MANIFEST);
+
+    let required = match (groups.is_empty(), manifest.is_file()) {
+        (false, _) => igs.with_dependencies(groups)?,
+        (true, true) => igs.with_dependencies(&Manifest::open(&manifest, igs.target())?.groups)?,
+        (true, false) => gitignore
+            .sections()
+            .into_iter()
+            .filter(|x| igs.get(x).is_ok())
+            .collect(),
+    };
+
+    let drifts = drifts(&mut gitignore, igs, &required)?;
+
+    if !drifts.is_empty() {
+        return Err(Error::Many(drifts));
+    }
+
+    println!(
+        "{}",
+        paint(GREEN, format!("{} group(s) verified.", required.len()))
+    );
+
+    return Ok(());
+}
+
+/// Every way the `required` sections of `gitignore` differ from what [`verify`] expects.
+pub(crate) fn drifts(
+    gitignore: &mut GitIgnore,
+    igs: &IgnoreGroups,
+    required: &[String],
+) -> Result<Vec<Error>, Error> {
+    let file_name = igs.target().file_name();
+    let unmanaged: Vec<String> = gitignore
+        .section_patterns(UNMANAGED)
+        .into_iter()
+        .map(|x| x.source)
+        .collect();
+    let mut drifts = Vec::new();
+
+    for name in required {
+        let content = match gitignore.section_content(name) {
+            Some(content) => content,
+            None => {
+                drifts.push(Error::Drift(format!(
+                    "'{name}' is missing from {file_name}."
+                )));
+                continue;
+            }
+        };
+
+        let current = content_hash(content);
+        let catalog = content_hash(&gitignore.pinned_content(igs.get(name)?)?);
+
+        if current != catalog {
+            let reason = match gitignore.section_meta(name) {
+                Some(meta) if meta.hash == current => "the catalog changed, run update",
+                _ => "it was modified locally",
+            };
+
+            drifts.push(Error::Drift(format!(
+                "'{name}' does not match the catalog, {reason}."
+            )));
+        }
+
+        let duplicates: Vec<String> = gitignore
+            .section_patterns(name)
+            .into_iter()
+            .map(|x| x.source)
+            .filter(|x| unmanaged.contains(x))
+            .collect();
+
+        if !duplicates.is_empty() {
+            drifts.push(Error::Drift(format!(
+                "{} outside of d-ig sections repeat '{name}'.",
+                duplicates.join(", ")
+            )));
+        }
+    }
+
+    return Ok(drifts);
+}
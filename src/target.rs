@@ -0,0 +1,69 @@
+use crate::{
+    pattern::Pattern,
+    r#const::{
+        CATALOG_SOURCE, DOCKER_CATALOG_SOURCE, DOCKER_IGNORE, DOCKER_IGNORE_FILE, GIT_IGNORE,
+        IGNORE_FILE, TARGET,
+    },
+    Error,
+};
+use std::path::Path;
+
+/// The kind of ignore file d-ig manages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Gitignore,
+    /// A Docker build context, matched from its root.
+    Dockerignore,
+}
+
+impl Target {
+    pub fn parse(value: &str) -> Result<Target, Error> {
+        match value {
+            "gitignore" => Ok(Target::Gitignore),
+            "dockerignore" => Ok(Target::Dockerignore),
+            _ => Err(Error::Usage(format!(
+                "Invalid {TARGET} choice \"{value}\". Expected gitignore or dockerignore."
+            ))),
+        }
+    }
+
+    /// The kind of ignore file at `path`, told by its name. Anything but a
+    /// `.dockerignore` is read as a .gitignore.
+    pub fn of(path: &Path) -> Target {
+        return match path.file_name().is_some_and(|x| x == DOCKER_IGNORE) {
+            true => Target::Dockerignore,
+            false => Target::Gitignore,
+        };
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Target::Gitignore => GIT_IGNORE,
+            Target::Dockerignore => DOCKER_IGNORE,
+        }
+    }
+
+    /// The embedded catalog of groups for the file.
+    pub fn catalog(self) -> &'static str {
+        match self {
+            Target::Gitignore => IGNORE_FILE,
+            Target::Dockerignore => DOCKER_IGNORE_FILE,
+        }
+    }
+
+    /// Where the catalog template for `group` comes from, recorded in section metadata.
+    pub fn template_source(self, group: &str) -> String {
+        match self {
+            Target::Gitignore => format!("{CATALOG_SOURCE}/{group}.gitignore"),
+            Target::Dockerignore => format!("{DOCKER_CATALOG_SOURCE}/{group}"),
+        }
+    }
+
+    /// Parses a single line of the file with its own matching rules.
+    pub fn pattern(self, line: &str) -> Option<Pattern> {
+        match self {
+            Target::Gitignore => Pattern::parse(line),
+            Target::Dockerignore => Pattern::parse_docker(line),
+        }
+    }
+}
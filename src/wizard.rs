@@ -0,0 +1,198 @@
+use crate::{
+    detect::detect,
+    gitignore::{cwd, GitIgnore, OnExists},
+    ignore_groups::IgnoreGroups,
+    output::paint,
+    plan::{Change, Plan},
+    r#const::{BLUE, GREEN, YELLOW},
+    target::Target,
+    Error,
+};
+use std::io::{stdin, stdout, IsTerminal, Write};
+
+/// Walks through the questions for a new .gitignore and shows the result. Returns the
+/// changes to create it with once confirmed, or `None` if the user backed out.
+pub fn create(
+    igs: &IgnoreGroups,
+    extra: Vec<Change>,
+    on_exists: OnExists,
+) -> Result<Option<Vec<Change>>, Error> {
+    let target = igs.target();
+    // The same file `--create` writes once the changes are confirmed.
+    let path = GitIgnore::build_location(&cwd()?, target)?;
+    let root = path.parent().unwrap_or(&path);
+
+    let mut gitignore = match (path.exists(), on_exists) {
+        (false, _) | (true, OnExists::Replace) => GitIgnore::new(&path),
+        (true, OnExists::Merge) => GitIgnore::merge(&path)?,
+        (true, OnExists::Fail) => {
+            return Err(Error::AlreadyPresent(format!(
+                "{} already exists at {}.",
+                target.file_name(),
+                path.to_string_lossy()
+            )))
+        }
+    };
+
+    let detection = detect(root);
+
+    let questions = [
+        ("Languages and frameworks", detection.languages),
+        ("Editors and IDEs", detection.editors),
+        ("Operating systems", detection.os),
+    ];
+
+    println!(
+        "Separate groups with commas, press enter to keep the suggestion or enter - for none.\n"
+    );
+
+    let mut changes = Vec::new();
+
+    for (question, suggestion) in questions {
+        let groups = match ask(igs, question, &suggestion)? {
+            Some(groups) => groups,
+            None => return Ok(None),
+        };
+
+        changes.extend(groups.into_iter().map(Change::AddG));
+    }
+
+    changes.extend(extra);
+
+    let plan = Plan::new(changes, &gitignore, igs);
+
+    if !plan.problems.is_empty() {
+        return Err(Error::Many(plan.problems));
+    }
+
+    let changes = plan.changes.clone();
+    plan.apply(&mut gitignore, igs)?;
+
+    println!(
+        "\n{}",
+        paint(BLUE, format!("##### {} #####", path.display()))
+    );
+    print!("{}", gitignore.render());
+    println!(
+        "{}\n",
+        paint(BLUE, format!("##### {} #####", path.display()))
+    );
+
+    let verb = match path.exists() {
+        true => "Write",
+        false => "Create",
+    };
+
+    return match prompt(&format!("{verb} {}? [y/N]", path.display()))? {
+        Some(answer) if answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes") => {
+            Ok(Some(changes))
+        }
+        _ => Ok(None),
+    };
+}
+
+/// Asks what to do with the .gitignore `--create` would write to when it already exists.
+/// Without a terminal to ask on, an existing file is an error. Returns `None` on abort.
+pub fn on_exists(target: Target) -> Result<Option<OnExists>, Error> {
+    let cwd = cwd()?;
+
+    let path = match GitIgnore::build_location(&cwd, target) {
+        Ok(path) => path,
+        Err(_) => return Ok(Some(OnExists::Fail)),
+    };
+
+    if !path.exists() || !stdin().is_terminal() {
+        return Ok(Some(OnExists::Fail));
+    }
+
+    loop {
+        let answer = match prompt(&format!(
+            "{} already exists. [m]erge, [r]eplace or [a]bort?",
+            path.display()
+        ))? {
+            Some(answer) => answer.to_ascii_lowercase(),
+            None => return Ok(None),
+        };
+
+        match answer.as_str() {
+            "m" | "merge" => return Ok(Some(OnExists::Merge)),
+            "r" | "replace" => return Ok(Some(OnExists::Replace)),
+            "a" | "abort" | "" => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Asks for groups until every answer resolves to the catalog.
+fn ask(
+    igs: &IgnoreGroups,
+    question: &str,
+    suggestion: &[String],
+) -> Result<Option<Vec<String>>, Error> {
+    loop {
+        let answer = match prompt(&format!(
+            "{} [{}]:",
+            paint(GREEN, question),
+            suggestion.join(", ")
+        ))? {
+            Some(answer) => answer,
+            None => return Ok(None),
+        };
+
+        if answer.is_empty() {
+            return Ok(Some(suggestion.to_vec()));
+        }
+
+        if answer == "-" {
+            return Ok(Some(Vec::new()));
+        }
+
+        let mut groups = Vec::new();
+        let mut valid = true;
+
+        for name in answer
+            .split(',')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+        {
+            match igs.lookup(name).as_slice() {
+                [group] => groups.push(group.clone()),
+                [] => {
+                    println!(
+                        "{}",
+                        paint(YELLOW, format!("No ignore group matches '{name}'."))
+                    );
+                    valid = false;
+                }
+                candidates => {
+                    println!(
+                        "{}",
+                        paint(
+                            YELLOW,
+                            format!("'{name}' could be any of: {}", candidates.join(", "))
+                        )
+                    );
+                    valid = false;
+                }
+            }
+        }
+
+        if valid {
+            return Ok(Some(groups));
+        }
+    }
+}
+
+/// Prints `question` and reads a trimmed line, returning `None` at the end of input.
+pub(crate) fn prompt(question: &str) -> Result<Option<String>, Error> {
+    print!("{question} ");
+    let _ = stdout().flush();
+
+    let mut line = String::new();
+
+    return match stdin().read_line(&mut line) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(line.trim().to_string())),
+        Err(err) => Err(Error::Io("Could not read answer".to_string(), err)),
+    };
+}
@@ -0,0 +1,197 @@
+use crate::{
+    detect::{entries, matching, LANGUAGE_MARKERS},
+    git_index::Repo,
+    gitignore::{cwd, GitIgnore},
+    header::HeaderStyle,
+    ignore_groups::IgnoreGroups,
+    output::paint,
+    plan::{Change, Plan},
+    r#const::{BLUE, GREEN, YELLOW, YES, YES_S},
+    wizard::prompt,
+    Error,
+};
+use std::{
+    io::{stdin, IsTerminal},
+    path::{Path, PathBuf},
+};
+
+/// Directories never searched for subprojects, on top of hidden ones.
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "vendor",
+    "dist",
+    "build",
+    "venv",
+    "__pycache__",
+];
+
+/// A subproject and the groups its .gitignore is missing.
+pub struct Project {
+    pub dir: PathBuf,
+    pub groups: Vec<String>,
+    pub gitignore: GitIgnore,
+    pub plan: Plan,
+}
+
+/// Finds every subproject in the repository, reports what its .gitignore needs and,
+/// once confirmed, writes them all.
+pub fn workspace(igs: &IgnoreGroups, yes: bool, style: Option<HeaderStyle>) -> Result<(), Error> {
+    let cwd = cwd()?;
+
+    let repo = match Repo::find(&cwd) {
+        Some(repo) => repo,
+        None => return Err(Error::NotFound("Could not find .git".to_string())),
+    };
+
+    let projects = projects(&repo.root, igs)?;
+
+    if projects.is_empty() {
+        println!("No subprojects found.");
+        return Ok(());
+    }
+
+    let mut pending = 0;
+
+    for project in &projects {
+        let shown = match project.dir.strip_prefix(&repo.root) {
+            Ok(dir) if dir.as_os_str().is_empty() => ".".to_string(),
+            Ok(dir) => dir.display().to_string(),
+            Err(_) => project.dir.display().to_string(),
+        };
+
+        let added: Vec<String> = project
+            .plan
+            .changes
+            .iter()
+            .filter_map(|x| match x {
+                Change::AddG(group) => Some(group.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let action = match (
+            added.is_empty(),
+            project.dir.join(igs.target().file_name()).exists(),
+        ) {
+            (true, _) => paint(GREEN, "up to date"),
+            (false, true) => paint(YELLOW, format!("add {}", added.join(", "))),
+            (false, false) => paint(YELLOW, format!("create with {}", added.join(", "))),
+        };
+
+        println!(
+            "{}  [{}]  {action}",
+            paint(BLUE, shown),
+            project.groups.join(", ")
+        );
+
+        for problem in &project.plan.problems {
+            println!("    {problem}");
+        }
+
+        if !added.is_empty() {
+            pending += 1;
+        }
+    }
+
+    if pending == 0 {
+        return Ok(());
+    }
+
+    if !yes {
+        if !stdin().is_terminal() {
+            println!("\nNothing was written. Run again with {YES}, {YES_S} to apply.");
+            return Ok(());
+        }
+
+        match prompt(&format!(
+            "\nUpdate {pending} {} file(s)? [y/N]",
+            igs.target().file_name()
+        ))? {
+            Some(answer)
+                if answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes") => {}
+            _ => {
+                println!("Nothing was written.");
+                return Ok(());
+            }
+        }
+    }
+
+    for project in projects {
+        if project.plan.changes.is_empty() {
+            continue;
+        }
+
+        let mut gitignore = project.gitignore;
+
+        if let Some(style) = style {
+            gitignore.set_style(style);
+        }
+
+        project.plan.apply(&mut gitignore, igs)?;
+        gitignore.write()?;
+    }
+
+    return Ok(());
+}
+
+/// Every directory under `root` holding a project manifest, with the groups it needs.
+pub fn projects(root: &Path, igs: &IgnoreGroups) -> Result<Vec<Project>, Error> {
+    let mut dirs = Vec::new();
+    find_dirs(root, &mut dirs);
+
+    let mut projects = Vec::new();
+
+    for dir in dirs {
+        let groups: Vec<String> = matching(&entries(&dir), LANGUAGE_MARKERS)
+            .into_iter()
+            .filter(|x| igs.get(x).is_ok())
+            .collect();
+
+        if groups.is_empty() {
+            continue;
+        }
+
+        let path = dir.join(igs.target().file_name());
+        let gitignore = match path.exists() {
+            true => GitIgnore::open(&path)?,
+            false => GitIgnore::new(&path),
+        };
+
+        let sections = gitignore.sections();
+        let changes = groups
+            .iter()
+            .filter(|x| !sections.contains(x))
+            .map(|x| Change::AddG(x.clone()))
+            .collect();
+
+        let plan = Plan::new(changes, &gitignore, igs);
+
+        projects.push(Project {
+            dir,
+            groups,
+            gitignore,
+            plan,
+        });
+    }
+
+    return Ok(projects);
+}
+
+/// Collects `dir` and every directory below it worth searching, parents first.
+fn find_dirs(dir: &Path, dirs: &mut Vec<PathBuf>) {
+    dirs.push(dir.to_path_buf());
+
+    let mut children: Vec<PathBuf> = entries(dir)
+        .into_iter()
+        .filter(|x| !x.starts_with('.') && !SKIPPED_DIRS.contains(&x.as_str()))
+        .map(|x| dir.join(x))
+        .filter(|x| x.is_dir() && !x.is_symlink())
+        .collect();
+
+    children.sort();
+
+    for child in children {
+        find_dirs(&child, dirs);
+    }
+}